@@ -0,0 +1,128 @@
+//! Measures heap allocations per received message.
+//!
+//! A client thread floods the server with small messages while the app is updated manually.
+//! Allocations made by the client thread are not counted and the allocations of an idle update
+//! are subtracted, so the result is the cost of receiving a single message.
+//!
+//! ```sh
+//! cargo run --release --example allocations
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{client::ClientRequestBuilder, connect, http::Uri, Message},
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static IGNORED: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !IGNORED.with(Cell::get) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const MESSAGES: usize = 10_000;
+const IDLE_UPDATES: usize = 1_000;
+
+#[derive(Resource, Default)]
+struct Received(usize);
+
+fn main() {
+    for (port, protocol) in [(42070, "bevy_websocket"), (42071, "bevy_websocket_raw")] {
+        let allocations = measure(port, protocol);
+        println!("{protocol}: {allocations:.2} allocations per message");
+    }
+}
+
+fn measure(port: u16, protocol: &'static str) -> f64 {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        WebSocketPlugin,
+        WebSocketServerPlugin::custom(WebSocketServerConfig {
+            addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port)),
+            ..default()
+        }),
+    ))
+    .init_resource::<Received>()
+    .add_systems(Update, count);
+    app.finish();
+    app.cleanup();
+
+    let client = thread::spawn(move || {
+        IGNORED.with(|ignored| ignored.set(true));
+        // wait for the listener
+        thread::sleep(Duration::from_millis(200));
+
+        let uri = Uri::try_from(format!("ws://127.0.0.1:{port}")).expect("Invalid uri");
+        let request = ClientRequestBuilder::new(uri).with_sub_protocol(protocol);
+        let (mut socket, _) = connect(request).expect("Failed to connect");
+
+        for _ in 0..MESSAGES {
+            socket
+                .send(Message::text("Hello World"))
+                .expect("Failed to send");
+        }
+        socket
+    });
+
+    // connect and warm up
+    while app.world().resource::<Received>().0 < 100 {
+        app.update();
+    }
+
+    let base = app.world().resource::<Received>().0;
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+    let mut updates = 0;
+    while app.world().resource::<Received>().0 < MESSAGES {
+        app.update();
+        updates += 1;
+    }
+    let total = (ALLOCATIONS.load(Ordering::Relaxed) - start) as f64;
+    let messages = (MESSAGES - base) as f64;
+
+    // keep the conversation open while measuring an idle update
+    let _socket = client.join();
+
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..IDLE_UPDATES {
+        app.update();
+    }
+    let idle = (ALLOCATIONS.load(Ordering::Relaxed) - start) as f64 / IDLE_UPDATES as f64;
+
+    (total - idle * updates as f64) / messages
+}
+
+fn count(
+    mut received: ResMut<Received>,
+    mut message_r: EventReader<WebSocketMessageEvent>,
+    mut raw_r: EventReader<WebSocketRawEvent>,
+) {
+    received.0 += message_r.read().count() + raw_r.read().count();
+}
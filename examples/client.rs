@@ -32,6 +32,7 @@ read all incoming messages but you're not able to send any.)"
     }
 }
 
+#[allow(clippy::result_large_err)]
 fn build_request(clients: &mut ResMut<WebSocketClients>) -> Result<(), tungstenite::Error> {
     let uri = {
         print!("uri (ws://, wss://): ");
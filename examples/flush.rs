@@ -0,0 +1,106 @@
+//! Measures a frame that sends many small messages with every [`FlushStrategy`].
+//!
+//! Reports the time per frame, the number of syscalls isn't counted.
+//!
+//! ```sh
+//! cargo run --release --example flush
+//! ```
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{client::ClientRequestBuilder, connect, http::Uri},
+};
+
+const PORT: u16 = 42072;
+const PEERS: usize = 50;
+const MESSAGES: usize = 100;
+const FRAMES: u32 = 20;
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        WebSocketPlugin,
+        WebSocketServerPlugin::custom(WebSocketServerConfig {
+            addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT)),
+            ..default()
+        }),
+    ))
+    .init_resource::<Opened>()
+    .add_systems(Update, count_opened);
+    app.finish();
+    app.cleanup();
+
+    let (done_s, done_r) = mpsc::channel::<()>();
+    let client = thread::spawn(move || {
+        // wait for the listener
+        thread::sleep(Duration::from_millis(200));
+
+        let uri = Uri::from_static("ws://127.0.0.1:42072");
+        let sockets: Vec<_> = (0..PEERS)
+            .map(|_| {
+                let request =
+                    ClientRequestBuilder::new(uri.clone()).with_sub_protocol("bevy_websocket");
+                connect(request).expect("Failed to connect")
+            })
+            .collect();
+
+        let _ = done_r.recv();
+        drop(sockets);
+    });
+
+    while app.world().resource::<Opened>().0 < PEERS {
+        app.update();
+    }
+
+    for strategy in [FlushStrategy::PerMessage, FlushStrategy::PerPeerPerFrame] {
+        app.world_mut()
+            .resource_mut::<WebSocketClients>()
+            .set_flush_strategy(strategy);
+
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..FRAMES {
+            let start = Instant::now();
+            app.world_mut()
+                .run_system_once(send)
+                .expect("Failed to send");
+            app.update();
+            elapsed += start.elapsed();
+        }
+
+        println!(
+            "{strategy:?}: {:?} per frame sending {MESSAGES} messages to {PEERS} peers",
+            elapsed / FRAMES
+        );
+    }
+
+    let _ = done_s.send(());
+    let _ = client.join();
+}
+
+#[derive(Resource, Default)]
+struct Opened(usize);
+
+fn count_opened(mut opened: ResMut<Opened>, mut open_r: EventReader<WebSocketOpenEvent>) {
+    opened.0 += open_r.read().count();
+}
+
+fn send(mut clients: ResMut<WebSocketClients>) {
+    let message = "x".repeat(32);
+
+    clients.for_each_mut(|_, writer| {
+        for _ in 0..MESSAGES {
+            writer
+                .send_message(message.as_str())
+                .expect("Failed to send");
+        }
+    });
+}
@@ -0,0 +1,101 @@
+//! Measures the cost of a frame with 0, 1 and 100 idle clients.
+//!
+//! ```sh
+//! cargo run --release --example overhead
+//! ```
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{client::ClientRequestBuilder, connect, http::Uri},
+};
+
+const PORT: u16 = 42073;
+const FRAMES: u32 = 2_000;
+const RUNS: usize = 5;
+
+#[derive(Resource, Default)]
+struct Opened(usize);
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.finish();
+    app.cleanup();
+    println!("without plugin: {:?} per frame", measure(&mut app));
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        WebSocketPlugin,
+        WebSocketServerPlugin::custom(WebSocketServerConfig {
+            addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT)),
+            ..default()
+        }),
+    ))
+    .init_resource::<Opened>()
+    .add_systems(Update, count_opened);
+    app.finish();
+    app.cleanup();
+
+    // wait for the listener
+    thread::sleep(Duration::from_millis(200));
+
+    let mut done = Vec::new();
+    let mut clients = 0;
+    for target in [0, 1, 100] {
+        let (done_s, done_r) = mpsc::channel::<()>();
+        let count = target - clients;
+        thread::spawn(move || {
+            let sockets: Vec<_> = (0..count)
+                .map(|_| {
+                    let request =
+                        ClientRequestBuilder::new(Uri::from_static("ws://127.0.0.1:42073"))
+                            .with_sub_protocol("bevy_websocket");
+                    connect(request).expect("Failed to connect")
+                })
+                .collect();
+
+            let _ = done_r.recv();
+            drop(sockets);
+        });
+        done.push(done_s);
+
+        while app.world().resource::<Opened>().0 < target {
+            app.update();
+        }
+        clients = target;
+
+        println!("{clients} clients: {:?} per frame", measure(&mut app));
+    }
+}
+
+fn measure(app: &mut App) -> Duration {
+    // warm up
+    for _ in 0..100 {
+        app.update();
+    }
+
+    // the fastest run is the least disturbed by the rest of the system
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..FRAMES {
+                app.update();
+            }
+            start.elapsed() / FRAMES
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn count_opened(mut opened: ResMut<Opened>, mut open_r: EventReader<WebSocketOpenEvent>) {
+    opened.0 += open_r.read().count();
+}
@@ -29,11 +29,13 @@ fn setup() {
 fn on_message(mut event: EventReader<WebSocketRawEvent>, mut clients: ResMut<WebSocketClients>) {
     for event in event.read() {
         if event.data.header().opcode == OpCode::Data(Data::Text) {
-            event
-                .reply(&mut clients)
-                .unwrap()
-                .send_raw(Frame::message("rawr 🐯", OpCode::Data(Data::Text), true))
-                .unwrap();
+            let Some(mut writer) = event.reply(&mut clients) else {
+                continue;
+            };
+            let frame = Frame::message("rawr 🐯", OpCode::Data(Data::Text), true);
+            if let Err(error) = writer.send_raw(frame) {
+                error!("Failed to reply to {}. - {error}", event.peer);
+            }
         }
     }
 }
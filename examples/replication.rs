@@ -0,0 +1,69 @@
+use std::{
+    env::current_dir,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+};
+
+use bevy::prelude::*;
+use bevy_websocket::prelude::*;
+use serde::Serialize;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            MinimalPlugins,
+            WebSocketPlugin,
+            WebSocketServerPlugin::custom(WebSocketServerConfig {
+                addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 42069)),
+                ..default()
+            }),
+            WebSocketReplicationPlugin,
+        ))
+        .replicate::<Position>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, orbit)
+        .run();
+}
+
+/// Position in the range of 0..1 that is rendered by replication.html.
+#[derive(Debug, Component, Serialize)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Debug, Component)]
+struct Orbit {
+    radius: f32,
+    speed: f32,
+}
+
+fn setup(mut commands: Commands) {
+    if let Ok(path) = current_dir() {
+        println!(
+            "Open file://{}/examples/replication.html to watch the dots.",
+            path.display()
+        );
+    } else {
+        println!("Open replication.html to watch the dots.")
+    }
+
+    for i in 1..=8 {
+        commands.spawn((
+            Replicated,
+            Position { x: 0.5, y: 0.5 },
+            Orbit {
+                radius: i as f32 * 0.05,
+                speed: 1.0 / i as f32,
+            },
+        ));
+    }
+}
+
+fn orbit(time: Res<Time>, mut query: Query<(&mut Position, &Orbit)>) {
+    for (mut position, orbit) in query.iter_mut() {
+        let angle = time.elapsed_secs() * orbit.speed;
+
+        position.x = 0.5 + angle.cos() * orbit.radius;
+        position.y = 0.5 + angle.sin() * orbit.radius;
+    }
+}
@@ -0,0 +1,130 @@
+//! Measures the message throughput of many clients with different numbers of workers, see
+//! [`WebSocketClients::set_workers`].
+//!
+//! A single worker reads one client per frame, more workers read every client each frame and
+//! should scale up to the number of cores.
+//!
+//! ```sh
+//! cargo run --release --example sharding
+//! ```
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{client::ClientRequestBuilder, connect, http::Uri, Message},
+};
+
+const PORT: u16 = 42074;
+const CLIENT_THREADS: usize = 4;
+const PEERS_PER_THREAD: usize = 64;
+const PEERS: usize = CLIENT_THREADS * PEERS_PER_THREAD;
+const MESSAGES: usize = 50;
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        WebSocketPlugin,
+        WebSocketServerPlugin::custom(WebSocketServerConfig {
+            addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT)),
+            request_queue_size: PEERS,
+            ..default()
+        }),
+    ))
+    .init_resource::<Counts>()
+    .add_systems(Update, count);
+    app.finish();
+    app.cleanup();
+
+    // every client thread sends a burst for each message it receives and reports when it's done
+    let (sent_s, sent_r) = mpsc::channel::<()>();
+    let round_s: Vec<_> = (0..CLIENT_THREADS)
+        .map(|_| {
+            let (round_s, round_r) = mpsc::channel::<()>();
+            let sent_s = sent_s.clone();
+            thread::spawn(move || {
+                // wait for the listener
+                thread::sleep(Duration::from_millis(200));
+
+                let uri = Uri::from_static("ws://127.0.0.1:42074");
+                let mut sockets: Vec<_> = (0..PEERS_PER_THREAD)
+                    .map(|_| {
+                        let request = ClientRequestBuilder::new(uri.clone())
+                            .with_sub_protocol("bevy_websocket");
+                        connect(request).expect("Failed to connect").0
+                    })
+                    .collect();
+
+                while round_r.recv().is_ok() {
+                    for socket in &mut sockets {
+                        for _ in 0..MESSAGES {
+                            socket
+                                .send(Message::text("x".repeat(32)))
+                                .expect("Failed to send");
+                        }
+                    }
+                    let _ = sent_s.send(());
+                }
+            });
+            round_s
+        })
+        .collect();
+
+    while app.world().resource::<Counts>().opened < PEERS {
+        app.update();
+    }
+
+    let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+    let workers = (0..)
+        .map(|exponent| 1 << exponent)
+        .take_while(|workers| *workers <= cores);
+    for workers in workers {
+        app.world_mut()
+            .resource_mut::<WebSocketClients>()
+            .set_workers(workers);
+        app.world_mut().resource_mut::<Counts>().received = 0;
+
+        // the messages are waiting in the sockets before the measurement starts
+        for round_s in &round_s {
+            round_s.send(()).expect("Client thread stopped");
+        }
+        for _ in 0..CLIENT_THREADS {
+            sent_r.recv().expect("Client thread stopped");
+        }
+
+        let start = Instant::now();
+        let mut frames = 0;
+        while app.world().resource::<Counts>().received < PEERS * MESSAGES {
+            app.update();
+            frames += 1;
+        }
+        let elapsed = start.elapsed();
+
+        println!(
+            "{workers} workers: {:.0} messages per second from {PEERS} peers in {frames} frames",
+            (PEERS * MESSAGES) as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Counts {
+    opened: usize,
+    received: usize,
+}
+
+fn count(
+    mut counts: ResMut<Counts>,
+    mut open_r: EventReader<WebSocketOpenEvent>,
+    mut message_r: EventReader<WebSocketMessageEvent>,
+) {
+    counts.opened += open_r.read().count();
+    counts.received += message_r.read().count();
+}
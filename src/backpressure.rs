@@ -0,0 +1,253 @@
+//! Limit the number of messages queued for slow clients.
+
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+
+use tungstenite::{Error, Message};
+
+use crate::{
+    client::{Client, WebSocketClients},
+    peer::WebSocketPeer,
+    writer::WebSocketWriter,
+};
+
+/// What [`WebSocketClients::write_with_backpressure`] does if the queue of a client is full.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BackpressureStrategy {
+    /// Discard the new message.
+    Drop,
+    /// Keep the new message in a backlog that is sent as soon as the client takes new data,
+    /// discarding the oldest message of the backlog once it is full.
+    DropOldest,
+    /// Wait until all queued messages have been sent, at most for
+    /// [`WebSocketClients::block_timeout`].
+    ///
+    /// This blocks the system that sends the message, and with it the frame. If the client
+    /// doesn't take the queued messages in time, the new message is dropped and
+    /// [`io::ErrorKind::TimedOut`] is returned.
+    ///
+    /// The timeout applies to every call. Sending a message to every client this way blocks for
+    /// up to the number of slow clients times the timeout.
+    Block,
+}
+
+/// Default of [`WebSocketClients::block_timeout`].
+pub const BLOCK_TIMEOUT: Duration = Duration::from_millis(100);
+
+impl Client {
+    /// Write messages of the backlog until the queue is full again.
+    ///
+    /// Messages that don't fit into the write buffer anymore are kept for the next attempt, so a
+    /// slow client only results in an error if the conversation itself failed.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn drain_backlog(&mut self, max_queued: usize) -> Result<(), Error> {
+        while self.queued < max_queued {
+            let Some(message) = self.backlog.pop_front() else {
+                break;
+            };
+
+            let result = WebSocketWriter { client: self }.send(message);
+            match result {
+                Ok(()) => (),
+                Err(Error::WriteBufferFull(message)) => {
+                    self.backlog.push_front(message);
+                    break;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue messages of [`WebSocketClients::write_with_deadline`] once the write buffer has been
+    /// sent, dropping those whose deadline has passed by `now`.
+    ///
+    /// Never waits for the socket. Returns the number of dropped messages.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn drain_deadline_backlog(&mut self, now: Instant) -> Result<u64, Error> {
+        let len = self.deadline_backlog.len();
+        self.deadline_backlog
+            .retain(|(_, deadline)| *deadline > now);
+        let dropped = (len - self.deadline_backlog.len()) as u64;
+
+        while !self.deadline_backlog.is_empty() {
+            match self.stream.flush() {
+                Ok(()) => self.queued = 0,
+                Err(Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+
+            if let Some((message, _)) = self.deadline_backlog.pop_front() {
+                WebSocketWriter { client: self }.send(message)?;
+            }
+        }
+        Ok(dropped)
+    }
+
+    /// Send all queued messages to the network, waiting for the socket to take them.
+    ///
+    /// Returns [`io::ErrorKind::TimedOut`] if they haven't been sent by `deadline`.
+    #[allow(clippy::result_large_err)]
+    fn flush_until(&mut self, deadline: Instant) -> Result<(), Error> {
+        loop {
+            match self.stream.flush() {
+                Ok(()) => break,
+                Err(Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(Error::Io(io::ErrorKind::TimedOut.into()));
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        self.queued = 0;
+        Ok(())
+    }
+}
+
+impl WebSocketClients {
+    /// Get the maximum number of messages queued for a client, see
+    /// [`WebSocketClients::write_with_backpressure`].
+    pub fn max_queued(&self) -> Option<usize> {
+        self.max_queued
+    }
+
+    /// Set the maximum number of messages queued for a client.
+    ///
+    /// Only [`WebSocketClients::write_with_backpressure`] respects the limit. [None], the default,
+    /// doesn't limit the number of queued messages.
+    pub fn set_max_queued(&mut self, max_queued: Option<usize>) {
+        self.max_queued = max_queued;
+    }
+
+    /// Get the maximum time [`BackpressureStrategy::Block`] waits for a client.
+    pub fn block_timeout(&self) -> Duration {
+        self.block_timeout.unwrap_or(BLOCK_TIMEOUT)
+    }
+
+    /// Set the maximum time [`BackpressureStrategy::Block`] waits for a client, the default is
+    /// [`BLOCK_TIMEOUT`].
+    pub fn set_block_timeout(&mut self, block_timeout: Duration) {
+        self.block_timeout = Some(block_timeout);
+    }
+
+    /// Send a message to a client unless [`WebSocketClients::max_queued`] messages haven't been
+    /// sent to the network yet, see [`WebSocketClients::count_messages_queued`].
+    ///
+    /// If the queue is full the message is handled according to `strategy`.
+    /// Returns `Ok(true)` if the message has been queued and `Ok(false)` if it has been dropped.
+    /// Slow clients never cause an error, except for [`BackpressureStrategy::Block`] timing out.
+    /// Returns [`Error::AlreadyClosed`] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::{prelude::*, tungstenite::Message};
+    /// fn send_position(mut clients: ResMut<WebSocketClients>, player: Single<&Transform>) {
+    ///     let message = Message::text(format!("{:?}", player.translation));
+    ///
+    ///     for (peer, _) in clients.snapshot().peers {
+    ///         // positions are outdated quickly, slow clients only get the newest ones
+    ///         let strategy = BackpressureStrategy::DropOldest;
+    ///         let _ = clients.write_with_backpressure(&peer, message.clone(), strategy);
+    ///     }
+    /// }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn write_with_backpressure(
+        &mut self,
+        target: &WebSocketPeer,
+        message: impl Into<Message>,
+        strategy: BackpressureStrategy,
+    ) -> Result<bool, Error> {
+        let max_queued = self.max_queued.unwrap_or(usize::MAX);
+        let block_timeout = self.block_timeout();
+        let client = self.inner.get_mut(target).ok_or(Error::AlreadyClosed)?;
+        client.drain_backlog(max_queued)?;
+
+        if client.queued < max_queued && client.backlog.is_empty() {
+            WebSocketWriter { client }.send(message.into())?;
+            return Ok(true);
+        }
+
+        match strategy {
+            BackpressureStrategy::Drop => Ok(false),
+            BackpressureStrategy::DropOldest => {
+                if client.backlog.len() >= max_queued {
+                    client.backlog.pop_front();
+                }
+                client.backlog.push_back(message.into());
+                Ok(true)
+            }
+            BackpressureStrategy::Block => {
+                client.flush_until(Instant::now() + block_timeout)?;
+                client.drain_backlog(max_queued)?;
+
+                WebSocketWriter { client }.send(message.into())?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Send a message to a client unless it can't be sent to the network before `deadline`.
+    ///
+    /// The message is only queued once everything queued before it has been sent, so it never
+    /// waits behind older data. Until then it is held back, and it is dropped if `deadline` passes
+    /// first. Dropped messages are counted by [`WebSocketStats::deadline_drops`]. Messages sent
+    /// without a deadline in the meantime go out first.
+    ///
+    /// This never blocks. Returns `Ok` if the message has been queued or is held back.
+    /// Returns [`io::ErrorKind::TimedOut`] if `deadline` has already passed, the message is
+    /// dropped then.
+    /// Returns [`Error::AlreadyClosed`] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// ```
+    /// # use std::time::{Duration, Instant};
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::{prelude::*, tungstenite::Message};
+    /// fn send_tick(mut clients: ResMut<WebSocketClients>, time: Res<Time>) {
+    ///     // a tick is worthless once the next one is due
+    ///     let deadline = Instant::now() + Duration::from_millis(50);
+    ///     let message = Message::text(time.elapsed_secs().to_string());
+    ///
+    ///     for (peer, _) in clients.snapshot().peers {
+    ///         if let Err(error) = clients.write_with_deadline(&peer, message.clone(), deadline) {
+    ///             error!("Failed to send tick to {peer}. - {error}");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// fn report_drops(stats: Res<WebSocketStats>) {
+    ///     if stats.is_changed() && stats.deadline_drops > 0 {
+    ///         warn!("{} ticks were dropped.", stats.deadline_drops);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`WebSocketStats::deadline_drops`]: crate::server::WebSocketStats::deadline_drops
+    #[allow(clippy::result_large_err)]
+    pub fn write_with_deadline(
+        &mut self,
+        target: &WebSocketPeer,
+        message: impl Into<Message>,
+        deadline: Instant,
+    ) -> Result<(), Error> {
+        let client = self.inner.get_mut(target).ok_or(Error::AlreadyClosed)?;
+        let now = Instant::now();
+        if now >= deadline {
+            self.deadline_drops += 1;
+            return Err(Error::Io(io::ErrorKind::TimedOut.into()));
+        }
+
+        client
+            .deadline_backlog
+            .push_back((message.into(), deadline));
+        let result = client.drain_deadline_backlog(now);
+        if let Ok(dropped) = result {
+            self.deadline_drops += dropped;
+        }
+        result.map(|_| ())
+    }
+}
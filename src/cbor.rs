@@ -0,0 +1,108 @@
+//! Send and receive values encoded as CBOR.
+
+use std::{fmt, io};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tungstenite::Error;
+
+use crate::{
+    client::WebSocketClients, events::WebSocketBinaryEvent, peer::WebSocketPeer,
+    writer::WebSocketWriter,
+};
+
+/// Error of sending a value encoded as CBOR.
+#[derive(Debug)]
+pub enum WebSocketCborError {
+    /// The value could not be encoded.
+    Encode(ciborium::ser::Error<io::Error>),
+    /// The message could not be sent.
+    Send(Error),
+}
+impl fmt::Display for WebSocketCborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Encode(error) => write!(f, "Failed to encode message. - {error}"),
+            Self::Send(error) => write!(f, "Failed to send message. - {error}"),
+        }
+    }
+}
+impl std::error::Error for WebSocketCborError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Encode(error) => Some(error),
+            Self::Send(error) => Some(error),
+        }
+    }
+}
+impl From<ciborium::ser::Error<io::Error>> for WebSocketCborError {
+    fn from(error: ciborium::ser::Error<io::Error>) -> Self {
+        Self::Encode(error)
+    }
+}
+impl From<Error> for WebSocketCborError {
+    fn from(error: Error) -> Self {
+        Self::Send(error)
+    }
+}
+
+impl WebSocketWriter<'_> {
+    /// Send `value` encoded as CBOR binary message to the conversation.
+    ///
+    /// Requires the `cbor` feature.
+    #[allow(clippy::result_large_err)]
+    pub fn send_cbor<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), WebSocketCborError> {
+        let mut data = Vec::new();
+        ciborium::into_writer(value, &mut data)?;
+        Ok(self.send_binary(data)?)
+    }
+}
+
+impl WebSocketClients {
+    /// Send `value` encoded as CBOR binary message to a client.
+    ///
+    /// Decode it on the other side with [`WebSocketBinaryEvent::decode_cbor`].
+    /// Returns [`Error::AlreadyClosed`] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// Requires the `cbor` feature.
+    #[allow(clippy::result_large_err)]
+    pub fn write_binary_json<T: Serialize + ?Sized>(
+        &mut self,
+        target: &WebSocketPeer,
+        value: &T,
+    ) -> Result<(), WebSocketCborError> {
+        self.write(target)
+            .ok_or(Error::AlreadyClosed)?
+            .send_cbor(value)
+    }
+}
+
+impl WebSocketBinaryEvent {
+    /// Decode the data of this event as CBOR.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// #[derive(serde::Deserialize)]
+    /// struct Input {
+    ///     x: f32,
+    ///     y: f32,
+    /// }
+    ///
+    /// fn read_input(mut binary_r: EventReader<WebSocketBinaryEvent>) {
+    ///     for event in binary_r.read() {
+    ///         match event.decode_cbor::<Input>() {
+    ///             Ok(input) => info!("{} moved to {}, {}", event.peer, input.x, input.y),
+    ///             Err(error) => warn!("Invalid input from {}. - {error}", event.peer),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Requires the `cbor` feature.
+    pub fn decode_cbor<T: DeserializeOwned>(&self) -> Result<T, ciborium::de::Error<io::Error>> {
+        ciborium::from_reader(&self.data[..])
+    }
+}
@@ -1,30 +1,329 @@
-use std::net::TcpStream;
+use std::{
+    collections::VecDeque,
+    io::{self, Cursor, Read},
+    net::TcpStream,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use bevy::prelude::*;
-use indexmap::IndexMap;
+use bevy::{
+    prelude::*,
+    render::extract_resource::{ExtractResource, ExtractResourcePlugin},
+};
+use bytes::{Buf, BytesMut};
+use indexmap::{IndexMap, IndexSet};
 use tungstenite::{
-    client::IntoClientRequest, connect, http::Response, protocol::frame::FrameSocket,
-    stream::MaybeTlsStream, Error, Message, WebSocket,
+    client::IntoClientRequest,
+    error::{CapacityError, ProtocolError},
+    http::Response,
+    protocol::{
+        frame::{coding::CloseCode, Frame, FrameHeader},
+        CloseFrame, WebSocketConfig,
+    },
+    stream::MaybeTlsStream,
+    Bytes, Error, Message, Utf8Bytes, WebSocket,
 };
 
-use crate::{events::*, peer::WebSocketPeer, writer::WebSocketWriter};
+#[cfg(feature = "secure")]
+use crate::secure::{SecureInbound, SecureSession, WebSocketSecureConfig};
+use crate::{
+    connector::{connect_client, ConnectedQueue},
+    events::*,
+    middleware::WebSocketMiddlewares,
+    pause::{hold, WebSocketPause},
+    peer::WebSocketPeer,
+    reconnect::Reconnect,
+    streaming::{stream_error_frame, StreamInbound, StreamState, WebSocketStreamConfig},
+    writer::{FlushStrategy, WebSocketWriter},
+    WebSocketPlugin,
+};
 
 #[derive(Debug)]
 pub(crate) struct Client {
     pub stream: WebSocket<MaybeTlsStream<TcpStream>>,
     pub mode: WebSocketClientMode,
+    /// Bytes received in [`WebSocketClientMode::Raw`] that do not form a complete frame yet.
+    pub raw_buffer: BytesMut,
+    /// Header of the frame currently received in [`WebSocketClientMode::Raw`].
+    pub raw_header: Option<(FrameHeader, u64)>,
+    /// Bytes are read into this buffer before they are appended to `raw_buffer`.
+    pub read_scratch: Box<[u8]>,
+    /// Time the last unanswered ping has been sent.
+    pub last_ping: Option<Instant>,
+    pub rtt: Option<Duration>,
+    pub flush_strategy: FlushStrategy,
+    /// Number of consecutive failed reads.
+    pub errors: u32,
+    /// Messages waiting for the queue to have room, see [`BackpressureStrategy::DropOldest`].
+    pub backlog: VecDeque<Message>,
+    /// Messages waiting for the write buffer to be sent, see
+    /// [`WebSocketClients::write_with_deadline`].
+    pub deadline_backlog: VecDeque<(Message, Instant)>,
+    /// Number of messages written since the write buffer has last been sent completely.
+    pub queued: usize,
+    pub streaming: Option<WebSocketStreamConfig>,
+    pub stream_state: StreamState,
+    /// Start of the current second and the number of raw frames read since then.
+    pub frame_window: (Instant, u32),
+    #[cfg(feature = "secure")]
+    pub secure: Option<SecureSession>,
+    /// Plaintext messages sent before the secure handshake has been completed.
+    #[cfg(feature = "secure")]
+    pub secure_backlog: Vec<Message>,
+}
+impl Client {
+    /// Wrap an established conversation.
+    ///
+    /// The stream is switched to non-blocking mode, so polling a conversation never stalls a frame.
+    /// Writes never block either: messages the socket can't take yet stay buffered and are sent
+    /// by a later flush, see [`Client::send`].
+    pub fn new(
+        stream: WebSocket<MaybeTlsStream<TcpStream>>,
+        mode: WebSocketClientMode,
+    ) -> Result<Self, io::Error> {
+        tcp_stream(stream.get_ref()).set_nonblocking(true)?;
+
+        Ok(Self {
+            stream,
+            mode,
+            raw_buffer: BytesMut::new(),
+            raw_header: None,
+            read_scratch: Box::default(),
+            last_ping: None,
+            rtt: None,
+            flush_strategy: FlushStrategy::default(),
+            errors: 0,
+            backlog: VecDeque::new(),
+            deadline_backlog: VecDeque::new(),
+            queued: 0,
+            streaming: None,
+            stream_state: StreamState::default(),
+            frame_window: (Instant::now(), 0),
+            #[cfg(feature = "secure")]
+            secure: None,
+            #[cfg(feature = "secure")]
+            secure_backlog: Vec::new(),
+        })
+    }
+
+    /// Send a message according to the [`FlushStrategy`] of this client.
+    ///
+    /// See [`Client::queue_result`] for how a full socket is handled.
+    #[allow(clippy::result_large_err)]
+    pub fn send(&mut self, message: Message) -> Result<(), Error> {
+        match self.flush_strategy {
+            FlushStrategy::PerMessage => self.send_now(message),
+            FlushStrategy::PerPeerPerFrame => {
+                let result = self.stream.write(message);
+                self.queue_result(result)
+            }
+        }
+    }
+
+    /// Send a message and everything queued before it to the network immediately.
+    ///
+    /// See [`Client::queue_result`] for how a full socket is handled.
+    #[allow(clippy::result_large_err)]
+    pub fn send_now(&mut self, message: Message) -> Result<(), Error> {
+        let result = self.stream.send(message);
+        if result.is_ok() {
+            self.queued = 0;
+            return Ok(());
+        }
+        self.queue_result(result)
+    }
+
+    /// Count a written message as queued.
+    ///
+    /// The stream is non-blocking, so [`io::ErrorKind::WouldBlock`] only means that the socket
+    /// can't take more data right now. tungstenite has buffered the message before that, and it
+    /// is sent by the next flush, so it counts as sent instead of failed.
+    #[allow(clippy::result_large_err)]
+    fn queue_result(&mut self, result: Result<(), Error>) -> Result<(), Error> {
+        match result {
+            Ok(()) => (),
+            Err(Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => (),
+            Err(error) => return Err(error),
+        }
+        self.queued += 1;
+        Ok(())
+    }
+
+    /// Read from the socket into the spare capacity of `raw_buffer`.
+    ///
+    /// At most as many bytes as have been reserved are read, so the buffer never grows here.
+    pub(crate) fn fill_raw_buffer(&mut self) -> io::Result<usize> {
+        if self.read_scratch.is_empty() {
+            let read_buffer_size = self.stream.get_config().read_buffer_size;
+            self.read_scratch = vec![0; read_buffer_size.max(14)].into_boxed_slice();
+        }
+
+        let spare = self.raw_buffer.capacity() - self.raw_buffer.len();
+        let len = spare.clamp(1, self.read_scratch.len());
+        let scratch = &mut self.read_scratch[..len];
+        let size = self.stream.get_mut().read(scratch)?;
+        self.raw_buffer.extend_from_slice(&scratch[..size]);
+
+        Ok(size)
+    }
+
+    /// Read a frame in [`WebSocketClientMode::Raw`].
+    ///
+    /// Returns [None] if no complete frame has been received yet.
+    ///
+    /// The read buffer is kept across calls, so frames are sliced out of it instead of being
+    /// copied into fresh allocations.
+    ///
+    /// Frame limits are always taken from the configuration of this conversation, not from
+    /// [`WebSocketServerConfig`], which might have been changed since it was accepted.
+    ///
+    /// [`WebSocketServerConfig`]: crate::server::WebSocketServerConfig
+    #[allow(clippy::result_large_err)]
+    fn read_raw(&mut self) -> Result<Option<Frame>, Error> {
+        let config = self.stream.get_config();
+        let max_size = config.max_frame_size.unwrap_or(usize::MAX);
+        if self.raw_buffer.capacity() == 0 {
+            self.raw_buffer.reserve(config.read_buffer_size);
+        }
+
+        loop {
+            if self.raw_header.is_none() {
+                let mut cursor = Cursor::new(&self.raw_buffer[..]);
+                self.raw_header = FrameHeader::parse(&mut cursor)?;
+                let advanced = cursor.position() as usize;
+                self.raw_buffer.advance(advanced);
+            }
+
+            if let Some((header, len)) = self.raw_header.take() {
+                let size = len as usize;
+                if size > max_size {
+                    return Err(Error::Capacity(CapacityError::MessageTooLong {
+                        size,
+                        max_size,
+                    }));
+                }
+
+                if size <= self.raw_buffer.len() {
+                    let payload = self.raw_buffer.split_to(size).freeze();
+                    return Ok(Some(Frame::from_payload(header, payload)));
+                }
+
+                self.raw_buffer.reserve(size - self.raw_buffer.len());
+                self.raw_header = Some((header, len));
+            } else {
+                // the smallest possible header
+                self.raw_buffer.reserve(2);
+            }
+
+            match self.fill_raw_buffer() {
+                Ok(0) => return Err(Error::Protocol(ProtocolError::ResetWithoutClosingHandshake)),
+                Ok(_) => (),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(error) => return Err(Error::Io(error)),
+            }
+        }
+    }
+}
+
+/// Get the [`TcpStream`] a [`MaybeTlsStream`] is based on.
+pub(crate) fn tcp_stream(stream: &MaybeTlsStream<TcpStream>) -> &TcpStream {
+    match stream {
+        MaybeTlsStream::Plain(stream) => stream,
+        #[cfg(feature = "rustls")]
+        MaybeTlsStream::Rustls(stream) => &stream.sock,
+        #[cfg(feature = "native-tls")]
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref(),
+        // because `MaybeTlsStream` implements #[non_exhaustive] we need to implement a &_ case.
+        _ => unreachable!("This should not happen."),
+    }
 }
 
 /// A client can operate in either Parsed or Raw mode.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Reflect)]
 pub enum WebSocketClientMode {
     Parsed,
     Raw,
 }
 
+/// How clients are removed from [`WebSocketClients`].
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum RemovalOrder {
+    /// Keep the order of the remaining clients.
+    ///
+    /// Removing a client moves all clients after it, which is O(n).
+    #[default]
+    Preserve,
+    /// Move the last client into the place of the removed one, which is O(1).
+    Fast,
+}
+
+/// The peers and modes of all clients at one point in time, see [`WebSocketClients::snapshot`].
+///
+/// [`WebSocketExtractPlugin`] extracts it into the render world every frame, so render systems can
+/// read it as a resource.
+///
+/// [`WebSocketClients`] can't be reflected because of the connections it owns, so
+/// [`WebSocketPlugin`] keeps this resource up to date in the main world instead. World inspectors
+/// show the clients through it.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// fn show_clients(snapshot: Res<WebSocketClientsSnapshot>) {
+///     for (peer, mode) in &snapshot.peers {
+///         info!("{peer} ({mode:?})");
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Reflect)]
+#[reflect(Resource, Default, Debug, PartialEq)]
+pub struct WebSocketClientsSnapshot {
+    pub peers: Vec<(WebSocketPeer, WebSocketClientMode)>,
+}
+impl ExtractResource for WebSocketClientsSnapshot {
+    type Source = WebSocketClients;
+
+    fn extract_resource(source: &Self::Source) -> Self {
+        source.snapshot()
+    }
+}
+
+/// This plugin extracts a [`WebSocketClientsSnapshot`] into the render world every frame.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// App::new().add_plugins((MinimalPlugins, WebSocketPlugin, WebSocketExtractPlugin));
+/// ```
+pub struct WebSocketExtractPlugin;
+impl Plugin for WebSocketExtractPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WebSocketPlugin>() {
+            panic!("WebSocketPlugin is required for WebSocketExtractPlugin");
+        }
+
+        app.add_plugins(ExtractResourcePlugin::<WebSocketClientsSnapshot>::default());
+    }
+}
+
+/// The outcome of [`WebSocketClients::write_all_with_stats`].
+#[derive(Debug, Default)]
+pub struct BroadcastResult {
+    /// Clients the message has been sent or queued to.
+    pub successful: Vec<WebSocketPeer>,
+    /// Clients the message could not be sent to.
+    pub failed: Vec<(WebSocketPeer, Error)>,
+    /// Size of the message times the number of successful sends.
+    ///
+    /// Framing and encryption overhead is not included.
+    pub bytes_sent: usize,
+}
+
 /// A map of active web-socket clients.
 ///
 /// ```
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
 /// fn send(mut clients: ResMut<WebSocketClients>) {
 ///     clients
 ///         .write(&"127.0.0.1:42069".parse().unwrap())
@@ -35,39 +334,476 @@ pub enum WebSocketClientMode {
 /// ```
 #[derive(Resource, Default)]
 pub struct WebSocketClients {
+    /// Index of the client that is polled next.
     iter_index: usize,
     pub(crate) inner: IndexMap<WebSocketPeer, Client>,
+    pub(crate) reconnect: IndexMap<WebSocketPeer, Reconnect>,
+    pub(crate) groups: IndexMap<String, IndexSet<WebSocketPeer>>,
+    flush_strategy: FlushStrategy,
+    pub(crate) max_queued: Option<usize>,
+    /// See [`WebSocketClients::block_timeout`], [None] uses [`BLOCK_TIMEOUT`].
+    ///
+    /// [`BLOCK_TIMEOUT`]: crate::backpressure::BLOCK_TIMEOUT
+    pub(crate) block_timeout: Option<Duration>,
+    /// Number of messages of [`WebSocketClients::write_with_deadline`] that missed their deadline.
+    pub(crate) deadline_drops: u64,
+    pub(crate) max_frames_per_second: Option<u32>,
+    /// See [`WebSocketClients::workers`].
+    pub(crate) workers: usize,
+    removal_order: RemovalOrder,
+    /// Streaming configuration of new clients.
+    pub(crate) streaming: Option<WebSocketStreamConfig>,
+    /// Close events of clients that have been removed outside of [`handle_clients`].
+    pub(crate) closed: Vec<WebSocketCloseEvent>,
+    /// Clients that are closed in [`WebSocketCleanupSet`].
+    disconnects: Vec<WebSocketPeer>,
+    /// Clients connected by [`WebSocketClients::connector`].
+    pub(crate) connected: ConnectedQueue,
+    /// Mode changes that haven't been sent as [`WebSocketModeChangeEvent`] yet.
+    pub(crate) mode_changes: Vec<WebSocketModeChangeEvent>,
+    #[cfg(feature = "secure")]
+    pub(crate) secure: Option<WebSocketSecureConfig>,
 }
 impl WebSocketClients {
-    #[allow(clippy::type_complexity)]
+    /// Create an empty map with memory for at least `capacity` clients.
+    ///
+    /// Insert it before adding [`WebSocketPlugin`], which keeps an existing resource, so the
+    /// server plugins configure this map instead of a default one.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// let mut app = App::new();
+    /// app.insert_resource(WebSocketClients::new_with_capacity(64))
+    ///     .add_plugins((MinimalPlugins, WebSocketPlugin, WebSocketServerPlugin));
+    ///
+    /// let clients = app.world().resource::<WebSocketClients>();
+    /// assert!(clients.is_empty());
+    /// assert!(clients.capacity() >= 64);
+    /// ```
+    ///
+    /// [`WebSocketPlugin`]: crate::WebSocketPlugin
+    pub fn new_with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: IndexMap::with_capacity(capacity),
+            ..default()
+        }
+    }
+
+    #[allow(clippy::type_complexity, clippy::result_large_err)]
     pub fn request<Req: IntoClientRequest>(
         &mut self,
         request: Req,
         mode: WebSocketClientMode,
     ) -> Result<(WebSocketPeer, Response<Option<Vec<u8>>>), Error> {
-        let (stream, response) = connect(request)?;
-        let peer = WebSocketPeer::from_maybe_tls_stream(stream.get_ref())?;
-
-        self.inner.insert(peer, Client { stream, mode });
+        let (peer, client, response) = connect_client(request, mode)?;
+        self.insert_connected(peer, client)?;
         Ok((peer, response))
     }
 
+    /// Add a conversation this app has established as a client.
+    ///
+    /// The secure handshake is started if [`WebSocketSecurePlugin`] was added.
+    ///
+    /// [`WebSocketSecurePlugin`]: crate::secure::WebSocketSecurePlugin
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn insert_connected(
+        &mut self,
+        peer: WebSocketPeer,
+        #[allow(unused_mut)] mut client: Client,
+    ) -> Result<(), Error> {
+        #[cfg(feature = "secure")]
+        self.start_secure_session(&mut client, true)?;
+
+        self.insert(peer, client);
+        Ok(())
+    }
+
+    pub(crate) fn insert(&mut self, peer: WebSocketPeer, mut client: Client) {
+        client.flush_strategy = self.flush_strategy;
+        client.streaming = self.streaming;
+        self.inner.insert(peer, client);
+    }
+
+    /// Get the number of clients.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns true if there are no clients.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     /// Create a [`WebSocketWriter`] for a client.
     ///
     /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
-    pub fn write(&mut self, target: &WebSocketPeer) -> Option<WebSocketWriter> {
-        self.inner.get_mut(target).map(|client| WebSocketWriter {
-            stream: &mut client.stream,
+    pub fn write(&mut self, target: &WebSocketPeer) -> Option<WebSocketWriter<'_>> {
+        self.inner
+            .get_mut(target)
+            .map(|client| WebSocketWriter { client })
+    }
+
+    /// Create a [`WebSocketWriter`] for the client at `index`.
+    ///
+    /// Clients are kept in the order they connected as long as [`RemovalOrder::Preserve`] is used.
+    /// Returns [None] if `index` is out of bounds.
+    pub fn write_to_index(&mut self, index: usize) -> Option<WebSocketWriter<'_>> {
+        self.inner
+            .get_index_mut(index)
+            .map(|(_, client)| WebSocketWriter { client })
+    }
+
+    /// Get the peer of the client at `index`.
+    ///
+    /// Returns [None] if `index` is out of bounds.
+    pub fn peer_at_index(&self, index: usize) -> Option<WebSocketPeer> {
+        self.inner.get_index(index).map(|(peer, _)| *peer)
+    }
+
+    /// Send a message to a client if it is still connected.
+    ///
+    /// Unlike [`WebSocketClients::write`], this sends the message right away and is meant for
+    /// cases where the send is the entire operation.
+    /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn greet(mut clients: ResMut<WebSocketClients>, mut open_r: EventReader<WebSocketOpenEvent>) {
+    ///     for event in open_r.read() {
+    ///         if let Some(Err(error)) = clients.write_if_connected(&event.peer, "Hello World") {
+    ///             error!("Failed to greet {}. - {error}", event.peer);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn write_if_connected(
+        &mut self,
+        target: &WebSocketPeer,
+        message: impl Into<Message>,
+    ) -> Option<Result<(), Error>> {
+        Some(self.write(target)?.send(message.into()))
+    }
+
+    /// Send a text message to a client only if it is in `mode`.
+    ///
+    /// Returns `Ok(false)` if the client is in another mode and `Ok(true)` if the message has
+    /// been sent.
+    /// Returns [`Error::AlreadyClosed`] if a client with the specified [`WebSocketPeer`] does not exist.
+    #[allow(clippy::result_large_err)]
+    pub fn write_text_if_mode(
+        &mut self,
+        target: &WebSocketPeer,
+        data: impl Into<Utf8Bytes>,
+        mode: WebSocketClientMode,
+    ) -> Result<bool, Error> {
+        let client = self.inner.get_mut(target).ok_or(Error::AlreadyClosed)?;
+        if client.mode != mode {
+            return Ok(false);
+        }
+
+        WebSocketWriter { client }.send_message(data)?;
+        Ok(true)
+    }
+
+    /// Get the peers and modes of all clients.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn detect_changes(clients: Res<WebSocketClients>, mut last: Local<WebSocketClientsSnapshot>) {
+    ///     let snapshot = clients.snapshot();
+    ///     if snapshot != *last {
+    ///         info!("Clients changed: {:?}", snapshot.peers);
+    ///         *last = snapshot;
+    ///     }
+    /// }
+    /// ```
+    pub fn snapshot(&self) -> WebSocketClientsSnapshot {
+        WebSocketClientsSnapshot {
+            peers: self
+                .inner
+                .iter()
+                .map(|(peer, client)| (*peer, client.mode))
+                .collect(),
+        }
+    }
+
+    /// Get the number of messages written to a client that haven't been sent to the network yet.
+    ///
+    /// Messages are queued until the end of the frame with [`FlushStrategy::PerPeerPerFrame`] and
+    /// while a slow client doesn't take new data. A growing count can be used to throttle sends,
+    /// see also [`WebSocketClients::write_with_backpressure`].
+    /// Returns 0 if a client with the specified [`WebSocketPeer`] does not exist.
+    pub fn count_messages_queued(&self, target: &WebSocketPeer) -> usize {
+        self.inner.get(target).map_or(0, |client| {
+            client.queued + client.backlog.len() + client.deadline_backlog.len()
         })
     }
 
+    /// Send a message to the first client.
+    ///
+    /// Clients are kept in the order they connected, so this is the oldest client unless
+    /// [`RemovalOrder::Fast`] is used and a client has been removed.
+    /// Returns [None] if there are no clients.
+    #[allow(clippy::result_large_err)]
+    pub fn write_to_first(&mut self, data: impl Into<Utf8Bytes>) -> Option<Result<(), Error>> {
+        self.inner
+            .first_mut()
+            .map(|(_, client)| WebSocketWriter { client }.send_message(data))
+    }
+
+    /// Send a message to the last client, which usually is the newest one.
+    ///
+    /// Returns [None] if there are no clients.
+    #[allow(clippy::result_large_err)]
+    pub fn write_to_last(&mut self, data: impl Into<Utf8Bytes>) -> Option<Result<(), Error>> {
+        self.inner
+            .last_mut()
+            .map(|(_, client)| WebSocketWriter { client }.send_message(data))
+    }
+
+    /// Call `f` with a [`WebSocketWriter`] for every client.
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&WebSocketPeer, &mut WebSocketWriter)) {
+        for (peer, client) in self.inner.iter_mut() {
+            f(peer, &mut WebSocketWriter { client });
+        }
+    }
+
+    /// Send a message to all clients and report the outcome for each of them.
+    ///
+    /// Messages that have been queued because the socket couldn't take them yet count as
+    /// successful.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn announce(mut clients: ResMut<WebSocketClients>) {
+    ///     let result = clients.write_all_with_stats("The server restarts in 5 minutes.");
+    ///     info!("Sent {} bytes to {} clients.", result.bytes_sent, result.successful.len());
+    ///
+    ///     for (peer, error) in result.failed {
+    ///         error!("Failed to notify {peer}. - {error}");
+    ///     }
+    /// }
+    /// ```
+    pub fn write_all_with_stats(&mut self, message: impl Into<Message>) -> BroadcastResult {
+        let message = message.into();
+        let mut result = BroadcastResult::default();
+
+        self.for_each_mut(|peer, writer| match writer.send(message.clone()) {
+            Ok(()) => result.successful.push(*peer),
+            Err(error) => result.failed.push((*peer, error)),
+        });
+
+        result.bytes_sent = message.len() * result.successful.len();
+        result
+    }
+
+    /// Reserve memory for at least `additional` more clients.
+    ///
+    /// Useful before a known wave of connections to avoid growing the map while accepting them.
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+
+    /// Get the number of clients that can be added without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Get the [`FlushStrategy`] used for all clients.
+    pub fn flush_strategy(&self) -> FlushStrategy {
+        self.flush_strategy
+    }
+
+    /// Set the [`FlushStrategy`] used for all clients.
+    pub fn set_flush_strategy(&mut self, strategy: FlushStrategy) {
+        self.flush_strategy = strategy;
+
+        for client in self.inner.values_mut() {
+            client.flush_strategy = strategy;
+        }
+    }
+
     /// Set the operation mode for a client.
     ///
+    /// A [`WebSocketModeChangeEvent`] is sent the next time messages are processed if the mode
+    /// changed.
     /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
     pub fn set_mode(&mut self, target: &WebSocketPeer, mode: WebSocketClientMode) -> Option<()> {
-        self.inner.get_mut(target).map(|client| {
+        let client = self.inner.get_mut(target)?;
+
+        if client.mode != mode {
+            self.mode_changes.push(WebSocketModeChangeEvent {
+                peer: *target,
+                old: client.mode,
+                new: mode,
+            });
             client.mode = mode;
-        })
+        }
+        Some(())
+    }
+
+    /// Set the operation mode of all clients.
+    ///
+    /// A [`WebSocketModeChangeEvent`] is sent for every changed client the next time messages are
+    /// processed. Returns the number of changed clients.
+    pub fn replace_mode_all(&mut self, mode: WebSocketClientMode) -> usize {
+        let before = self.mode_changes.len();
+
+        for (peer, client) in self.inner.iter_mut() {
+            if client.mode != mode {
+                self.mode_changes.push(WebSocketModeChangeEvent {
+                    peer: *peer,
+                    old: client.mode,
+                    new: mode,
+                });
+                client.mode = mode;
+            }
+        }
+
+        self.mode_changes.len() - before
+    }
+
+    /// Send a ping to a client.
+    ///
+    /// The time the ping has been sent is recorded to measure the round-trip time.
+    /// Returns [`Error::AlreadyClosed`] if a client with the specified [`WebSocketPeer`] does not exist.
+    #[allow(clippy::result_large_err)]
+    pub fn ping_peer(
+        &mut self,
+        target: &WebSocketPeer,
+        data: impl Into<Bytes>,
+    ) -> Result<(), Error> {
+        self.write(target)
+            .ok_or(Error::AlreadyClosed)?
+            .send_ping(data)
+    }
+
+    /// Get the round-trip time of the last answered ping of a client.
+    ///
+    /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist or no ping has been answered yet.
+    pub fn rtt(&self, target: &WebSocketPeer) -> Option<Duration> {
+        self.inner.get(target).and_then(|client| client.rtt)
+    }
+
+    /// Get the configuration a client has been established with.
+    ///
+    /// Accepted conversations use [`WebSocketServerConfig::websocket_config`] at the time they
+    /// have been accepted, conversations established with [`WebSocketClients::request`] use the
+    /// default configuration.
+    /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// [`WebSocketServerConfig::websocket_config`]: crate::server::WebSocketServerConfig::websocket_config
+    pub fn get_config(&self, target: &WebSocketPeer) -> Option<&WebSocketConfig> {
+        self.inner
+            .get(target)
+            .map(|client| client.stream.get_config())
+    }
+
+    /// Remove all clients for which `f` returns false.
+    ///
+    /// Removed clients are sent a close frame and a [`WebSocketCloseEvent`] is sent for each of
+    /// them in [`WebSocketCleanupSet`]. They are not reconnected.
+    pub fn retain(&mut self, mut f: impl FnMut(&WebSocketPeer, WebSocketClientMode) -> bool) {
+        let closed = &mut self.closed;
+        let cursor = self.iter_index;
+        let mut index = 0;
+        let mut removed_before_cursor = 0;
+        let mut removed = Vec::new();
+
+        // `IndexMap::retain` keeps the order of the remaining clients
+        self.inner.retain(|peer, client| {
+            index += 1;
+            if f(peer, client.mode) {
+                return true;
+            }
+
+            if index <= cursor {
+                removed_before_cursor += 1;
+            }
+
+            let frame = CloseFrame {
+                code: CloseCode::Normal,
+                reason: "".into(),
+            };
+            if let Err(error) = client.stream.close(Some(frame.clone())) {
+                debug!("Failed to close conversation with {peer}. - {error}");
+            }
+
+            closed.push(WebSocketCloseEvent {
+                data: Some(frame),
+                peer: *peer,
+            });
+            removed.push(*peer);
+            false
+        });
+
+        self.iter_index -= removed_before_cursor;
+        for peer in removed {
+            self.leave_all_groups(&peer);
+            self.reconnect.swap_remove(&peer);
+        }
+    }
+
+    /// Close the conversation with a client.
+    ///
+    /// The client is sent a close frame and removed in [`WebSocketCleanupSet`] of the current
+    /// frame, a [`WebSocketCloseEvent`] is sent for it at the same time.
+    /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
+    pub fn disconnect(&mut self, target: &WebSocketPeer) -> Option<()> {
+        if !self.inner.contains_key(target) {
+            return None;
+        }
+
+        if !self.disconnects.contains(target) {
+            self.disconnects.push(*target);
+        }
+        // the conversation is closed on purpose
+        self.reconnect.swap_remove(target);
+        Some(())
+    }
+
+    /// Get how clients are removed.
+    pub fn removal_order(&self) -> RemovalOrder {
+        self.removal_order
+    }
+
+    /// Set how clients are removed, see [`RemovalOrder`].
+    pub fn set_removal_order(&mut self, removal_order: RemovalOrder) {
+        self.removal_order = removal_order;
+    }
+
+    /// Remove a client according to the [`RemovalOrder`].
+    ///
+    /// The polling cursor is moved so that every remaining client is still polled once per cycle.
+    pub(crate) fn remove(&mut self, peer: &WebSocketPeer) -> Option<Client> {
+        self.leave_all_groups(peer);
+
+        match self.removal_order {
+            RemovalOrder::Preserve => {
+                let (index, _, client) = self.inner.shift_remove_full(peer)?;
+
+                // the clients after the removed one moved one place to the front
+                if index < self.iter_index {
+                    self.iter_index -= 1;
+                }
+                Some(client)
+            }
+            RemovalOrder::Fast => {
+                let (index, _, client) = self.inner.swap_remove_full(peer)?;
+
+                // the last client took the place of the removed one, if it hasn't been polled in
+                // this cycle yet but is now behind the cursor, it is moved in front of the cursor
+                if index < self.iter_index && self.iter_index <= self.inner.len() {
+                    self.inner.swap_indices(index, self.iter_index - 1);
+                    self.iter_index -= 1;
+                }
+                Some(client)
+            }
+        }
     }
 
     pub(crate) fn next(&mut self) -> Option<(&WebSocketPeer, &mut Client)> {
@@ -75,60 +811,465 @@ impl WebSocketClients {
             return None;
         }
 
-        self.iter_index = (self.iter_index + 1) % self.inner.len();
-        self.inner.get_index_mut(self.iter_index)
+        let index = self.iter_index % self.inner.len();
+        if index == 0 && self.iter_index != 0 && self.inner.len() > 1 {
+            warn_once!(
+                "WebSocketPlugin polls one client per frame, so each of the {} clients is only \
+                 read every {} frames.",
+                self.inner.len(),
+                self.inner.len()
+            );
+        }
+        self.iter_index = index + 1;
+        self.inner.get_index_mut(index)
     }
 }
 
-pub(crate) fn handle_clients(
+/// How errors reading from a conversation are handled.
+///
+/// Applies to all conversations, whether they are parsed, streamed or raw. Conversations are
+/// always closed once the connection has been lost, the strategy only decides about the
+/// remaining errors, like invalid UTF-8, a protocol violation or a message that is too big.
+///
+/// A conversation that is kept after a protocol violation or an oversized frame might not be
+/// able to make sense of the following data either, so every following read can fail as well.
+/// [`WebSocketErrorStrategy::Retry`] limits how long such a conversation is kept.
+#[derive(Resource, Clone, Default)]
+pub enum WebSocketErrorStrategy {
+    /// Log the error and close the conversation.
+    #[default]
+    Disconnect,
+    /// Log the error and keep the conversation.
+    Log,
+    /// Log the error and close the conversation after this many consecutive errors.
+    Retry(u32),
+    /// Call a function instead of logging the error, the conversation is closed if it returns
+    /// true.
+    Custom(Arc<dyn Fn(WebSocketPeer, Error) -> bool + Send + Sync>),
+}
+impl WebSocketErrorStrategy {
+    /// Handle the `errors`th consecutive read error of `peer`.
+    ///
+    /// Returns true if the conversation has to be closed.
+    fn disconnects(&self, peer: WebSocketPeer, error: Error, errors: u32) -> bool {
+        let lost = is_lost(&error);
+        let disconnect = match self {
+            Self::Disconnect => true,
+            Self::Log => false,
+            Self::Retry(retries) => errors > *retries,
+            Self::Custom(f) => return f(peer, error) || lost,
+        };
+
+        if lost {
+            warn!("Lost connection to {peer}. - {error}");
+        } else if disconnect {
+            warn!("Closing conversation with {peer}. - {error}");
+        } else {
+            warn!("Failed to read from {peer}. - {error}");
+        }
+        lost || disconnect
+    }
+}
+impl std::fmt::Debug for WebSocketErrorStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Disconnect => write!(f, "Disconnect"),
+            Self::Log => write!(f, "Log"),
+            Self::Retry(retries) => f.debug_tuple("Retry").field(retries).finish(),
+            Self::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// Returns true if `error` means that the connection is gone.
+fn is_lost(error: &Error) -> bool {
+    match error {
+        Error::ConnectionClosed | Error::AlreadyClosed => true,
+        Error::Protocol(ProtocolError::ResetWithoutClosingHandshake) => true,
+        Error::Io(error) => matches!(
+            error.kind(),
+            io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::UnexpectedEof
+        ),
+        _ => false,
+    }
+}
+
+/// Handle an error reading from `client` according to `strategy`.
+///
+/// Returns the close event of the conversation if it has to be removed.
+fn read_error(
+    client: &mut Client,
+    peer: WebSocketPeer,
+    error: Error,
+    strategy: &WebSocketErrorStrategy,
+) -> Option<WebSocketCloseEvent> {
+    client.errors += 1;
+
+    let data = stream_error_frame(&error);
+    if !strategy.disconnects(peer, error, client.errors) {
+        return None;
+    }
+
+    if let Some(frame) = &data {
+        let _ = client.stream.close(Some(frame.clone()));
+    }
+    Some(WebSocketCloseEvent { data, peer })
+}
+
+/// System set in [`PostUpdate`] that removes clients closed by [`WebSocketClients::disconnect`]
+/// or [`WebSocketClients::retain`] and sends their [`WebSocketCloseEvent`]s.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WebSocketCleanupSet;
+
+/// Run condition that skips the cleanup while no client has been closed.
+pub(crate) fn has_disconnects(clients: Res<WebSocketClients>) -> bool {
+    !clients.disconnects.is_empty() || !clients.closed.is_empty()
+}
+
+pub(crate) fn cleanup_clients(
     mut clients: ResMut<WebSocketClients>,
-    mut message_w: EventWriter<WebSocketMessageEvent>,
-    mut binary_w: EventWriter<WebSocketBinaryEvent>,
-    mut pong_w: EventWriter<WebSocketPongEvent>,
-    mut raw_w: EventWriter<WebSocketRawEvent>,
     mut close_w: EventWriter<WebSocketCloseEvent>,
 ) {
-    if let Some((peer, client)) = clients.next() {
-        let peer = *peer;
+    let disconnects = std::mem::take(&mut clients.disconnects);
+
+    for peer in disconnects {
+        let Some(mut client) = clients.remove(&peer) else {
+            continue;
+        };
+
+        let frame = CloseFrame {
+            code: CloseCode::Normal,
+            reason: "".into(),
+        };
+        if let Err(error) = client.stream.close(Some(frame.clone())) {
+            debug!("Failed to close conversation with {peer}. - {error}");
+        }
+
+        close_w.send(WebSocketCloseEvent {
+            data: Some(frame),
+            peer,
+        });
+    }
+
+    close_w.send_batch(clients.closed.drain(..));
+}
+
+/// Run condition that is true while at least one client is connected.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// fn send_time(mut clients: ResMut<WebSocketClients>, time: Res<Time>) {
+///     let message = time.elapsed_secs().to_string();
+///     clients.for_each_mut(|_, writer| {
+///         let _ = writer.send_message(message.as_str());
+///     });
+/// }
+///
+/// App::new()
+///     .add_plugins((MinimalPlugins, WebSocketPlugin))
+///     .add_systems(Update, send_time.run_if(at_least_one_client));
+/// ```
+pub fn at_least_one_client(clients: Res<WebSocketClients>) -> bool {
+    !clients.is_empty()
+}
+
+/// Run condition that skips message handling while there is nothing to do.
+pub(crate) fn has_clients(clients: Res<WebSocketClients>) -> bool {
+    !clients.inner.is_empty()
+        || !clients.closed.is_empty()
+        || !clients.mode_changes.is_empty()
+        || !clients.connected.receiver.is_empty()
+}
 
-        match client.mode {
+/// What [`Client::poll`] has received.
+pub(crate) enum Polled {
+    /// A message of a parsed conversation, pings have already been answered.
+    Message(Message),
+    Chunk(WebSocketStreamChunkEvent),
+    Raw(Frame),
+    /// A raw frame has been dropped, contains the number of frames read this second.
+    FrameLimitExceeded(u32),
+    /// The handshake has been completed, contains the remote static key.
+    #[cfg(feature = "secure")]
+    Secured(Vec<u8>),
+    /// The conversation is over and the client has to be removed.
+    Closed(WebSocketCloseEvent),
+}
+
+impl Client {
+    /// Read from this conversation once.
+    ///
+    /// Only this client is touched, so clients can be polled on different threads.
+    /// Returns [None] if nothing has been received.
+    pub(crate) fn poll(
+        &mut self,
+        peer: WebSocketPeer,
+        error_strategy: &WebSocketErrorStrategy,
+        max_frames_per_second: Option<u32>,
+    ) -> Option<Polled> {
+        match self.mode {
             WebSocketClientMode::Parsed => {
-                if let Ok(msg) = client.stream.read() {
-                    match msg {
-                        Message::Text(data) => {
-                            message_w.send(WebSocketMessageEvent {
-                                data: data.to_string(),
+                let msg = if self.is_streaming() {
+                    match self.read_streaming() {
+                        Ok(Some(StreamInbound::Message(msg))) => msg,
+                        Ok(Some(StreamInbound::Chunk {
+                            stream_id,
+                            chunk,
+                            first,
+                            last,
+                        })) => {
+                            self.errors = 0;
+                            return Some(Polled::Chunk(WebSocketStreamChunkEvent {
                                 peer,
-                            });
+                                stream_id,
+                                chunk,
+                                first,
+                                last,
+                            }));
                         }
-                        Message::Binary(data) => {
-                            binary_w.send(WebSocketBinaryEvent { data, peer });
+                        Ok(None) => return None,
+                        Err(error) => {
+                            return read_error(self, peer, error, error_strategy)
+                                .map(Polled::Closed)
                         }
-                        Message::Ping(data) => {
-                            if client.stream.send(Message::Pong(data)).is_err() {
-                                error!("Failed to reply to ping.");
-                            }
+                    }
+                } else {
+                    match self.stream.read() {
+                        Ok(msg) => msg,
+                        Err(Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => {
+                            return None
                         }
-                        Message::Pong(data) => {
-                            pong_w.send(WebSocketPongEvent { data, peer });
+                        Err(error) => {
+                            return read_error(self, peer, error, error_strategy)
+                                .map(Polled::Closed)
                         }
-                        Message::Close(data) => {
-                            clients.inner.swap_remove(&peer);
+                    }
+                };
+                self.errors = 0;
+
+                #[cfg(feature = "secure")]
+                let msg = match self.secure_inbound(msg) {
+                    Ok(SecureInbound::Message(msg)) => msg,
+                    Ok(SecureInbound::Secured(remote_public_key)) => {
+                        return Some(Polled::Secured(remote_public_key))
+                    }
+                    Ok(SecureInbound::Pending) => return None,
+                    Err(frame) => {
+                        return Some(Polled::Closed(WebSocketCloseEvent {
+                            data: Some(frame),
+                            peer,
+                        }))
+                    }
+                };
 
-                            close_w.send(WebSocketCloseEvent { data, peer });
+                match msg {
+                    Message::Ping(data) => {
+                        if let Err(error) = self.send_now(Message::Pong(data)) {
+                            error!("Failed to reply to ping. - {error}");
                         }
-                        _ => (),
-                    };
+                        None
+                    }
+                    Message::Pong(data) => {
+                        if let Some(sent) = self.last_ping.take() {
+                            self.rtt = Some(sent.elapsed());
+                        }
+                        Some(Polled::Message(Message::Pong(data)))
+                    }
+                    Message::Close(data) => {
+                        Some(Polled::Closed(WebSocketCloseEvent { data, peer }))
+                    }
+                    msg => Some(Polled::Message(msg)),
                 }
             }
             WebSocketClientMode::Raw => {
-                let max_size = client.stream.get_config().max_frame_size;
-                let mut reader = FrameSocket::new(client.stream.get_mut());
+                let data = match self.read_raw() {
+                    Ok(Some(data)) => data,
+                    Ok(None) => return None,
+                    Err(error) => {
+                        return read_error(self, peer, error, error_strategy).map(Polled::Closed)
+                    }
+                };
+                self.errors = 0;
 
-                if let Ok(Some(data)) = reader.read(max_size) {
-                    raw_w.send(WebSocketRawEvent { data, peer });
+                let exceeded = max_frames_per_second.and_then(|max| self.count_frame(max));
+                match exceeded {
+                    Some(frames_this_second) => {
+                        Some(Polled::FrameLimitExceeded(frames_this_second))
+                    }
+                    None => Some(Polled::Raw(data)),
                 }
             }
         }
     }
+
+    /// Send the backlogs and flush the write buffer of this client at the end of a frame.
+    ///
+    /// Returns the number of messages that missed their deadline, see
+    /// [`WebSocketClients::write_with_deadline`].
+    pub(crate) fn flush_frame(
+        &mut self,
+        peer: WebSocketPeer,
+        max_queued: usize,
+        now: Instant,
+    ) -> u64 {
+        if let Err(error) = self.drain_backlog(max_queued) {
+            error!("Failed to send backlog to {peer}. - {error}");
+        }
+        let deadline_drops = match self.drain_deadline_backlog(now) {
+            Ok(dropped) => dropped,
+            Err(error) => {
+                error!("Failed to send messages with a deadline to {peer}. - {error}");
+                0
+            }
+        };
+
+        match self.stream.flush() {
+            Ok(()) => self.queued = 0,
+            Err(Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => (),
+            Err(error) => error!("Failed to flush messages to {peer}. - {error}"),
+        }
+        deadline_drops
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_clients(
+    mut clients: ResMut<WebSocketClients>,
+    mut message_w: EventWriter<WebSocketMessageEvent>,
+    mut binary_w: EventWriter<WebSocketBinaryEvent>,
+    mut pong_w: EventWriter<WebSocketPongEvent>,
+    mut raw_w: EventWriter<WebSocketRawEvent>,
+    mut close_w: EventWriter<WebSocketCloseEvent>,
+    mut chunk_w: EventWriter<WebSocketStreamChunkEvent>,
+    mut mode_w: EventWriter<WebSocketModeChangeEvent>,
+    mut frame_limit_w: EventWriter<WebSocketFrameLimitExceededEvent>,
+    mut pause: Option<ResMut<WebSocketPause>>,
+    middleware: Option<Res<WebSocketMiddlewares>>,
+    error_strategy: Res<WebSocketErrorStrategy>,
+    #[cfg(feature = "secure")] mut secured_w: EventWriter<WebSocketSecuredEvent>,
+) {
+    close_w.send_batch(clients.closed.drain(..));
+    mode_w.send_batch(clients.mode_changes.drain(..));
+
+    while let Ok((peer, client)) = clients.connected.receiver.try_recv() {
+        if let Err(error) = clients.insert_connected(peer, client) {
+            error!("Failed to start secure session with {peer}. - {error}");
+        }
+    }
+
+    // `has_clients` also runs this system to send the events above
+    if clients.is_empty() {
+        return;
+    }
+
+    let error_strategy = &*error_strategy;
+    let max_frames_per_second = clients.max_frames_per_second;
+    let (polled, sharded) = if clients.workers() > 1 {
+        (None, clients.poll_shards(error_strategy))
+    } else {
+        let polled = clients.next().and_then(|(peer, client)| {
+            let peer = *peer;
+            client
+                .poll(peer, error_strategy, max_frames_per_second)
+                .map(|polled| (peer, polled))
+        });
+        (polled, Vec::new())
+    };
+
+    for (peer, polled) in polled.into_iter().chain(sharded.into_iter().flatten()) {
+        match polled {
+            Polled::Message(Message::Text(data)) => {
+                let mut event = WebSocketMessageEvent { data, peer };
+                if middleware
+                    .as_ref()
+                    .is_some_and(|middleware| !middleware.apply(&mut event))
+                {
+                    continue;
+                }
+
+                if let Some(event) = hold(&mut pause, event) {
+                    message_w.send(event);
+                }
+            }
+            Polled::Message(Message::Binary(data)) => {
+                let event = WebSocketBinaryEvent { data, peer };
+                if let Some(event) = hold(&mut pause, event) {
+                    binary_w.send(event);
+                }
+            }
+            Polled::Message(Message::Pong(data)) => {
+                pong_w.send(WebSocketPongEvent { data, peer });
+            }
+            // tungstenite doesn't produce these when reading, but forward them instead of losing
+            // them silently
+            Polled::Message(Message::Frame(data)) => {
+                debug!("Received a raw frame from {peer} in parsed mode.");
+
+                if let Some(event) = hold(&mut pause, WebSocketRawEvent { data, peer }) {
+                    raw_w.send(event);
+                }
+            }
+            // answered or turned into `Polled::Closed` by `Client::poll`
+            Polled::Message(Message::Ping(_) | Message::Close(_)) => (),
+            Polled::Chunk(event) => {
+                if let Some(event) = hold(&mut pause, event) {
+                    chunk_w.send(event);
+                }
+            }
+            Polled::Raw(data) => {
+                if let Some(event) = hold(&mut pause, WebSocketRawEvent { data, peer }) {
+                    raw_w.send(event);
+                }
+            }
+            Polled::FrameLimitExceeded(frames_this_second) => {
+                frame_limit_w.send(WebSocketFrameLimitExceededEvent {
+                    peer,
+                    frames_this_second,
+                });
+            }
+            #[cfg(feature = "secure")]
+            Polled::Secured(remote_public_key) => {
+                info!("Secured conversation with: {}", peer);
+
+                secured_w.send(WebSocketSecuredEvent {
+                    peer,
+                    remote_public_key,
+                });
+            }
+            Polled::Closed(event) => {
+                clients.remove(&peer);
+
+                close_w.send(event);
+            }
+        }
+    }
+}
+
+/// Keep the [`WebSocketClientsSnapshot`] of the main world up to date.
+pub(crate) fn update_snapshot(
+    clients: Res<WebSocketClients>,
+    mut snapshot: ResMut<WebSocketClientsSnapshot>,
+) {
+    snapshot.set_if_neq(clients.snapshot());
+}
+
+/// Send all messages that have been buffered this frame.
+pub(crate) fn flush_clients(mut clients: ResMut<WebSocketClients>) {
+    let deadline_drops = if clients.workers() > 1 {
+        clients.flush_shards()
+    } else {
+        let max_queued = clients.max_queued.unwrap_or(usize::MAX);
+        let now = Instant::now();
+        clients
+            .inner
+            .iter_mut()
+            .map(|(peer, client)| client.flush_frame(*peer, max_queued, now))
+            .sum()
+    };
+    clients.deadline_drops += deadline_drops;
 }
@@ -0,0 +1,57 @@
+//! Named close codes of [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-7.4.1).
+//!
+//! Each constant converts into the matching [`CloseCode`] with [`CloseCode::from`].
+//!
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_websocket::{close_codes::{self, CloseCode}, prelude::*};
+//! fn on_close(mut close_r: EventReader<WebSocketCloseEvent>) {
+//!     for event in close_r.read() {
+//!         if let Some(frame) = &event.data {
+//!             if frame.code == CloseCode::from(close_codes::POLICY_VIOLATION) {
+//!                 warn!("{} violated our policy: {}", event.peer, frame.reason);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+pub use tungstenite::protocol::frame::coding::CloseCode;
+
+/// The purpose of the connection has been fulfilled, see [`CloseCode::Normal`].
+pub const NORMAL_CLOSURE: u16 = 1000;
+/// The endpoint is going away, e.g. a server shutting down, see [`CloseCode::Away`].
+pub const GOING_AWAY: u16 = 1001;
+/// The endpoint received a message that violates the protocol, see [`CloseCode::Protocol`].
+pub const PROTOCOL_ERROR: u16 = 1002;
+/// The endpoint received a type of data it can't accept, see [`CloseCode::Unsupported`].
+pub const UNSUPPORTED_DATA: u16 = 1003;
+/// No close code was present, see [`CloseCode::Status`].
+///
+/// Only reported locally, it must not be sent in a close frame.
+pub const NO_STATUS_RECEIVED: u16 = 1005;
+/// The connection was closed without a close frame, see [`CloseCode::Abnormal`].
+///
+/// Only reported locally, it must not be sent in a close frame.
+pub const ABNORMAL_CLOSURE: u16 = 1006;
+/// The endpoint received data that isn't consistent with the type of the message, e.g. invalid
+/// UTF-8 in a text message, see [`CloseCode::Invalid`].
+pub const INVALID_PAYLOAD: u16 = 1007;
+/// The endpoint received a message that violates its policy, see [`CloseCode::Policy`].
+pub const POLICY_VIOLATION: u16 = 1008;
+/// The endpoint received a message that is too big to process, see [`CloseCode::Size`].
+pub const MESSAGE_TOO_BIG: u16 = 1009;
+/// The client expected the server to negotiate an extension, see [`CloseCode::Extension`].
+pub const MANDATORY_EXTENSION: u16 = 1010;
+/// The server encountered an unexpected condition, see [`CloseCode::Error`].
+pub const INTERNAL_ERROR: u16 = 1011;
+/// The server is restarting, see [`CloseCode::Restart`].
+pub const SERVICE_RESTART: u16 = 1012;
+/// The server is overloaded, clients should reconnect later, see [`CloseCode::Again`].
+pub const TRY_AGAIN_LATER: u16 = 1013;
+/// A gateway received an invalid response from the upstream server, see [`CloseCode::Bad`].
+pub const BAD_GATEWAY: u16 = 1014;
+/// The TLS handshake failed, see [`CloseCode::Tls`].
+///
+/// Only reported locally, it must not be sent in a close frame.
+pub const TLS_HANDSHAKE: u16 = 1015;
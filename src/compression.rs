@@ -0,0 +1,61 @@
+//! Opportunistic compression of text messages.
+//!
+//! Compressed messages are sent as binary messages which start with [`COMPRESSED`], followed by
+//! the raw deflate (RFC 1951) encoded UTF-8 text. Browsers can decode them with
+//! `new DecompressionStream("deflate-raw")`.
+//! This doesn't require `permessage-deflate` to be negotiated.
+
+use std::io::{self, Write};
+
+use flate2::{write::DeflateEncoder, Compression};
+use tungstenite::{Error, Utf8Bytes};
+
+use crate::{client::WebSocketClients, peer::WebSocketPeer, writer::WebSocketWriter};
+
+/// Marks a binary message as deflate compressed text.
+pub const COMPRESSED: u8 = 0x01;
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let mut encoder = DeflateEncoder::new(vec![COMPRESSED], Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+impl WebSocketWriter<'_> {
+    /// Send a message to the conversation, compressed if it is at least `threshold` bytes long.
+    ///
+    /// The message is sent uncompressed if compressing it doesn't make it smaller, e.g. for
+    /// random data.
+    ///
+    /// Requires the `compression` feature.
+    #[allow(clippy::result_large_err)]
+    pub fn send_with_compression(&mut self, data: &str, threshold: usize) -> Result<(), Error> {
+        if data.len() >= threshold {
+            let compressed = compress(data.as_bytes())?;
+            if compressed.len() < data.len() {
+                return self.send_binary(compressed);
+            }
+        }
+
+        self.send_message(Utf8Bytes::from(data))
+    }
+}
+
+impl WebSocketClients {
+    /// Send a message to a client, compressed if it is at least `threshold` bytes long and
+    /// compressing makes it smaller.
+    ///
+    /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// Requires the `compression` feature.
+    #[allow(clippy::result_large_err)]
+    pub fn write_with_compression(
+        &mut self,
+        target: &WebSocketPeer,
+        data: &str,
+        threshold: usize,
+    ) -> Option<Result<(), Error>> {
+        self.write(target)
+            .map(|mut writer| writer.send_with_compression(data, threshold))
+    }
+}
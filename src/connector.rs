@@ -0,0 +1,155 @@
+//! Connect to servers without blocking the schedule.
+
+use std::{
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+    thread,
+};
+
+use bevy::tasks::futures_lite::future;
+use crossbeam_channel::{Receiver, Sender};
+use tungstenite::{
+    client::{connect_with_config, IntoClientRequest},
+    http::Response,
+    protocol::WebSocketConfig,
+    Error,
+};
+
+use crate::{
+    client::{Client, WebSocketClientMode, WebSocketClients},
+    peer::WebSocketPeer,
+    server::MAX_WRITE_BUFFER_SIZE,
+};
+
+/// The result of connecting to a server on a separate thread.
+pub(crate) type Connected = Result<(WebSocketPeer, Client), Error>;
+
+/// Connect to a server, blocking until the handshake has been completed.
+///
+/// The write buffer is limited to [`MAX_WRITE_BUFFER_SIZE`], like the one of accepted
+/// conversations.
+#[allow(clippy::type_complexity, clippy::result_large_err)]
+pub(crate) fn connect_client<Req: IntoClientRequest>(
+    request: Req,
+    mode: WebSocketClientMode,
+) -> Result<(WebSocketPeer, Client, Response<Option<Vec<u8>>>), Error> {
+    let config = WebSocketConfig::default().max_write_buffer_size(MAX_WRITE_BUFFER_SIZE);
+    let (stream, response) = connect_with_config(request, Some(config), 3)?;
+    let peer = WebSocketPeer::from_maybe_tls_stream(stream.get_ref())?;
+    let client = Client::new(stream, mode)?;
+
+    Ok((peer, client, response))
+}
+
+/// Connect to a server on a new thread.
+///
+/// The result can be polled from the returned receiver once the handshake has been completed.
+pub(crate) fn connect_in_background<Req: IntoClientRequest + Send + 'static>(
+    request: Req,
+    mode: WebSocketClientMode,
+) -> Receiver<Connected> {
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+    thread::spawn(move || {
+        let result = connect_client(request, mode).map(|(peer, client, _)| (peer, client));
+        // the app has been dropped or the attempt has been cancelled
+        let _ = sender.send(result);
+    });
+    receiver
+}
+
+/// Clients connected by a [`WebSocketConnector`] that haven't been added yet.
+pub(crate) struct ConnectedQueue {
+    sender: Sender<(WebSocketPeer, Client)>,
+    pub receiver: Receiver<(WebSocketPeer, Client)>,
+}
+impl Default for ConnectedQueue {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        Self { sender, receiver }
+    }
+}
+
+/// Connect to servers without blocking, see [`WebSocketClients::connector`].
+#[derive(Clone)]
+pub struct WebSocketConnector {
+    sender: Sender<(WebSocketPeer, Client)>,
+}
+impl WebSocketConnector {
+    /// Connect to a server.
+    ///
+    /// The connection is established on a separate thread, so awaiting the returned future
+    /// doesn't block the task. The client is added to [`WebSocketClients`] the next time messages
+    /// are processed.
+    #[allow(clippy::type_complexity, clippy::result_large_err)]
+    pub async fn request_async<Req: IntoClientRequest + Send + 'static>(
+        &self,
+        request: Req,
+        mode: WebSocketClientMode,
+    ) -> Result<(WebSocketPeer, Response<Option<Vec<u8>>>), Error> {
+        let (peer, client, response) = unblock(move || connect_client(request, mode)).await?;
+
+        // the app has been dropped
+        self.sender
+            .send((peer, client))
+            .map_err(|_| Error::AlreadyClosed)?;
+        Ok((peer, response))
+    }
+}
+
+/// Run `f` on a new thread and wait for its result.
+async fn unblock<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    let state: Arc<Mutex<(Option<T>, Option<Waker>)>> = Arc::new(Mutex::new((None, None)));
+
+    {
+        let state = state.clone();
+        thread::spawn(move || {
+            let result = f();
+
+            let mut state = state.lock().unwrap_or_else(|error| error.into_inner());
+            state.0 = Some(result);
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        });
+    }
+
+    future::poll_fn(|cx| {
+        let mut state = state.lock().unwrap_or_else(|error| error.into_inner());
+        match state.0.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    })
+    .await
+}
+
+impl WebSocketClients {
+    /// Get a [`WebSocketConnector`] to connect to servers from async tasks.
+    ///
+    /// ```no_run
+    /// # use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
+    /// # use bevy_websocket::{prelude::*, tungstenite::{client::ClientRequestBuilder, http::Uri}};
+    /// fn connect(clients: Res<WebSocketClients>) {
+    ///     let connector = clients.connector();
+    ///     let request = ClientRequestBuilder::new(Uri::from_static("ws://127.0.0.1:42069"))
+    ///         .with_sub_protocol("bevy_websocket");
+    ///
+    ///     AsyncComputeTaskPool::get()
+    ///         .spawn(async move {
+    ///             match connector.request_async(request, WebSocketClientMode::Parsed).await {
+    ///                 Ok((peer, _)) => info!("Connected to {peer}."),
+    ///                 Err(error) => error!("Failed to connect. - {error}"),
+    ///             }
+    ///         })
+    ///         .detach();
+    /// }
+    /// ```
+    pub fn connector(&self) -> WebSocketConnector {
+        WebSocketConnector {
+            sender: self.connected.sender.clone(),
+        }
+    }
+}
@@ -1,8 +1,10 @@
+use std::{net::SocketAddr, sync::Arc};
+
 use bevy::prelude::*;
 use tungstenite::{
-    http::{HeaderMap, HeaderValue},
+    http::{header::AsHeaderName, HeaderMap, HeaderValue},
     protocol::{frame::Frame, CloseFrame},
-    Bytes,
+    Bytes, Utf8Bytes,
 };
 
 use crate::{
@@ -48,7 +50,7 @@ impl_reply!(
 /// This event represents text messages.
 #[derive(Event, Debug)]
 pub struct WebSocketMessageEvent {
-    pub data: String,
+    pub data: Utf8Bytes,
     pub peer: WebSocketPeer,
 }
 
@@ -72,13 +74,46 @@ pub struct WebSocketRawEvent {
     pub data: Frame,
     pub peer: WebSocketPeer,
 }
+impl WebSocketRawEvent {
+    /// Returns true if the frame is the last one of a message.
+    pub fn is_fin(&self) -> bool {
+        self.data.header().is_final
+    }
+}
+
+/// This event represents a chunk of a large message that is received in chunks.
+///
+/// See [`crate::streaming`].
+#[derive(Event, Debug)]
+pub struct WebSocketStreamChunkEvent {
+    pub peer: WebSocketPeer,
+    /// Identifies the message of this chunk within the conversation.
+    pub stream_id: u64,
+    pub chunk: Bytes,
+    /// The first chunk of the message.
+    pub first: bool,
+    /// The last chunk of the message.
+    pub last: bool,
+}
 
 /// This event represents that a new conversation has been established.
+///
+/// With the `secure` feature, the secure handshake is still running when this event is sent.
+/// Messages sent to the conversation are held back until `WebSocketSecuredEvent` is sent.
 #[derive(Event, Debug)]
 pub struct WebSocketOpenEvent {
     pub peer: WebSocketPeer,
     pub mode: WebSocketClientMode,
-    pub headers: HeaderMap<HeaderValue>,
+    /// Headers of the handshake request.
+    pub headers: Arc<HeaderMap<HeaderValue>>,
+}
+impl WebSocketOpenEvent {
+    /// Get a header of the handshake request.
+    ///
+    /// Returns [None] if the header does not exist or is not valid ASCII.
+    pub fn header(&self, name: impl AsHeaderName) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
 }
 
 /// This event represents that a conversation has been closed.
@@ -87,3 +122,70 @@ pub struct WebSocketCloseEvent {
     pub data: Option<CloseFrame>,
     pub peer: WebSocketPeer,
 }
+
+/// This event represents that the operation mode of a client has been changed with
+/// [`WebSocketClients::set_mode`] or [`WebSocketClients::replace_mode_all`].
+#[derive(Event, Debug)]
+pub struct WebSocketModeChangeEvent {
+    pub peer: WebSocketPeer,
+    pub old: WebSocketClientMode,
+    pub new: WebSocketClientMode,
+}
+
+/// This event represents that a conversation registered with
+/// [`WebSocketClients::reconnect_on_close`] has been reestablished.
+#[derive(Event, Debug)]
+pub struct WebSocketReconnectEvent {
+    /// The peer of the closed conversation.
+    pub old: WebSocketPeer,
+    /// The peer of the new conversation. Usually equal to `old`.
+    pub new: WebSocketPeer,
+}
+
+/// This event represents that reconnecting a conversation registered with
+/// [`WebSocketClients::reconnect_on_close`] has been given up after
+/// [`WebSocketReconnectPolicy::max_attempts`].
+///
+/// [`WebSocketReconnectPolicy::max_attempts`]: crate::reconnect::WebSocketReconnectPolicy::max_attempts
+#[derive(Event, Debug)]
+pub struct WebSocketReconnectFailedEvent {
+    /// The peer of the closed conversation.
+    pub peer: WebSocketPeer,
+    /// Number of failed attempts.
+    pub attempts: u32,
+    /// The error of the last attempt.
+    pub error: String,
+}
+
+/// This event represents that a client in [`WebSocketClientMode::Raw`] has sent more frames
+/// than [`WebSocketClients::max_frames_per_second`].
+///
+/// The frame has been dropped.
+#[derive(Event, Debug)]
+pub struct WebSocketFrameLimitExceededEvent {
+    pub peer: WebSocketPeer,
+    /// Number of frames received from the client this second, including the dropped ones.
+    pub frames_this_second: u32,
+}
+
+/// This event represents that the server is listening for connections.
+///
+/// Sent once when [`WebSocketServerPlugin`] has started its listener.
+///
+/// [`WebSocketServerPlugin`]: crate::WebSocketServerPlugin
+#[derive(Event, Debug)]
+pub struct WebSocketServerReadyEvent {
+    /// The address the server is listening on.
+    pub addr: SocketAddr,
+}
+
+/// This event represents that the secure handshake of a conversation has been completed.
+///
+/// Requires the `secure` feature.
+#[cfg(feature = "secure")]
+#[derive(Event, Debug)]
+pub struct WebSocketSecuredEvent {
+    pub peer: WebSocketPeer,
+    /// The verified static public key of the peer.
+    pub remote_public_key: Vec<u8>,
+}
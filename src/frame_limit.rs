@@ -0,0 +1,63 @@
+//! Limit the number of raw frames clients can send.
+
+use std::time::{Duration, Instant};
+
+use crate::client::{Client, WebSocketClients};
+
+impl Client {
+    /// Count a frame read in [`WebSocketClientMode::Raw`].
+    ///
+    /// Returns the number of frames read this second if it exceeds `max_frames_per_second`.
+    ///
+    /// [`WebSocketClientMode::Raw`]: crate::client::WebSocketClientMode::Raw
+    pub(crate) fn count_frame(&mut self, max_frames_per_second: u32) -> Option<u32> {
+        let now = Instant::now();
+        let (start, frames) = &mut self.frame_window;
+        if now.duration_since(*start) >= Duration::from_secs(1) {
+            *start = now;
+            *frames = 0;
+        }
+
+        *frames += 1;
+        (*frames > max_frames_per_second).then_some(*frames)
+    }
+}
+
+impl WebSocketClients {
+    /// Get the maximum number of raw frames a client can send per second, see
+    /// [`WebSocketClients::set_max_frames_per_second`].
+    pub fn max_frames_per_second(&self) -> Option<u32> {
+        self.max_frames_per_second
+    }
+
+    /// Set the maximum number of raw frames a client can send per second.
+    ///
+    /// Only clients in [`WebSocketClientMode::Raw`] are limited. Frames over the limit are dropped
+    /// and a [`WebSocketFrameLimitExceededEvent`] is sent for each of them. [None], the default,
+    /// doesn't limit the number of frames.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn limit_frames(mut clients: ResMut<WebSocketClients>) {
+    ///     clients.set_max_frames_per_second(Some(120));
+    /// }
+    ///
+    /// fn kick_flooders(
+    ///     mut clients: ResMut<WebSocketClients>,
+    ///     mut limit_r: EventReader<WebSocketFrameLimitExceededEvent>,
+    /// ) {
+    ///     for event in limit_r.read() {
+    ///         if event.frames_this_second > 240 {
+    ///             clients.disconnect(&event.peer);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`WebSocketClientMode::Raw`]: crate::client::WebSocketClientMode::Raw
+    /// [`WebSocketFrameLimitExceededEvent`]: crate::events::WebSocketFrameLimitExceededEvent
+    pub fn set_max_frames_per_second(&mut self, max_frames_per_second: Option<u32>) {
+        self.max_frames_per_second = max_frames_per_second;
+    }
+}
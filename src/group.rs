@@ -0,0 +1,122 @@
+//! Named groups of clients.
+
+use tungstenite::{protocol::frame::Frame, Error};
+
+use crate::{client::WebSocketClients, peer::WebSocketPeer, writer::WebSocketWriter};
+
+impl WebSocketClients {
+    /// Add a client to a group.
+    ///
+    /// Groups are created when their first client joins and removed when their last client
+    /// leaves. Clients leave all groups when their conversation is closed.
+    /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn join_lobby(mut clients: ResMut<WebSocketClients>, mut open_r: EventReader<WebSocketOpenEvent>) {
+    ///     for event in open_r.read() {
+    ///         clients.join_group(&event.peer, "lobby");
+    ///     }
+    /// }
+    /// ```
+    pub fn join_group(&mut self, target: &WebSocketPeer, group: impl Into<String>) -> Option<()> {
+        if !self.inner.contains_key(target) {
+            return None;
+        }
+
+        self.groups.entry(group.into()).or_default().insert(*target);
+        Some(())
+    }
+
+    /// Remove a client from a group.
+    ///
+    /// Returns [None] if the client is not a member of the group.
+    pub fn leave_group(&mut self, target: &WebSocketPeer, group: &str) -> Option<()> {
+        let members = self.groups.get_mut(group)?;
+        if !members.shift_remove(target) {
+            return None;
+        }
+
+        if members.is_empty() {
+            self.groups.shift_remove(group);
+        }
+        Some(())
+    }
+
+    /// Get all members of a group in the order they joined.
+    ///
+    /// Returns an empty [Vec] if the group does not exist.
+    pub fn all_peers_in_group(&self, group: &str) -> Vec<WebSocketPeer> {
+        self.groups
+            .get(group)
+            .map(|members| members.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the names of all groups a client is a member of.
+    pub fn groups_for_peer(&self, target: &WebSocketPeer) -> Vec<String> {
+        self.groups
+            .iter()
+            .filter(|(_, members)| members.contains(target))
+            .map(|(group, _)| group.clone())
+            .collect()
+    }
+
+    /// Call `f` with a [`WebSocketWriter`] for every member of a group in the order they joined.
+    ///
+    /// Does nothing if the group does not exist.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn greet_lobby(mut clients: ResMut<WebSocketClients>) {
+    ///     clients.for_each_in_group_mut("lobby", |peer, writer| {
+    ///         if let Err(error) = writer.send_message("Hello lobby!") {
+    ///             error!("Failed to greet {peer}. - {error}");
+    ///         }
+    ///     });
+    /// }
+    /// ```
+    pub fn for_each_in_group_mut(
+        &mut self,
+        group: &str,
+        mut f: impl FnMut(&WebSocketPeer, &mut WebSocketWriter),
+    ) {
+        let Some(members) = self.groups.get(group) else {
+            return;
+        };
+
+        for peer in members {
+            if let Some(client) = self.inner.get_mut(peer) {
+                f(peer, &mut WebSocketWriter { client });
+            }
+        }
+    }
+
+    /// Send a raw [`Frame`] to all members of a group.
+    ///
+    /// The payload of the frame is shared by all members instead of being copied.
+    /// Returns the members the frame could not be sent to.
+    pub fn write_raw_to_group(&mut self, group: &str, frame: Frame) -> Vec<(WebSocketPeer, Error)> {
+        let Some(members) = self.groups.get(group) else {
+            return Vec::new();
+        };
+
+        members
+            .iter()
+            .filter_map(|peer| {
+                let client = self.inner.get_mut(peer)?;
+                let result = WebSocketWriter { client }.send_raw(frame.clone());
+                result.err().map(|error| (*peer, error))
+            })
+            .collect()
+    }
+
+    pub(crate) fn leave_all_groups(&mut self, target: &WebSocketPeer) {
+        self.groups.retain(|_, members| {
+            members.shift_remove(target);
+            !members.is_empty()
+        });
+    }
+}
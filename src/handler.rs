@@ -0,0 +1,60 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::{
+    client::{handle_clients, WebSocketClients},
+    events::WebSocketMessageEvent,
+    server::MessageSchedule,
+    writer::WebSocketWriter,
+};
+
+/// Register handlers for incoming text messages.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// fn on_ping(event: &WebSocketMessageEvent, writer: &mut WebSocketWriter) {
+///     if event.data == "ping" {
+///         writer.send_message("pong").unwrap();
+///     }
+/// }
+///
+/// App::new()
+///     .add_plugins((MinimalPlugins, WebSocketPlugin))
+///     .add_websocket_handler(on_ping);
+/// ```
+pub trait WebSocketAppExt {
+    /// Call `handler` with every [`WebSocketMessageEvent`] and a [`WebSocketWriter`] for
+    /// the conversation the message belongs to.
+    ///
+    /// The handler runs in the schedule messages are processed in, right after they have been
+    /// received. Add the server plugin first if it processes messages in a custom schedule.
+    ///
+    /// The handler is skipped if the conversation has been closed already.
+    fn add_websocket_handler<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&WebSocketMessageEvent, &mut WebSocketWriter) + Send + Sync + 'static;
+}
+impl WebSocketAppExt for App {
+    fn add_websocket_handler<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&WebSocketMessageEvent, &mut WebSocketWriter) + Send + Sync + 'static,
+    {
+        let schedule = self
+            .world()
+            .get_resource::<MessageSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+
+        self.add_systems(
+            schedule,
+            (move |mut events: EventReader<WebSocketMessageEvent>,
+                   mut clients: ResMut<WebSocketClients>| {
+                for event in events.read() {
+                    if let Some(mut writer) = event.reply(&mut clients) {
+                        handler(event, &mut writer);
+                    }
+                }
+            })
+            .after(handle_clients),
+        )
+    }
+}
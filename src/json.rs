@@ -0,0 +1,141 @@
+//! Send values serialized as JSON.
+
+use std::fmt;
+
+use serde::Serialize;
+use tungstenite::{Error, Utf8Bytes};
+
+use crate::{client::WebSocketClients, peer::WebSocketPeer, writer::WebSocketWriter};
+
+/// Error of sending a serialized value.
+#[derive(Debug)]
+pub enum WebSocketSendError {
+    /// The value could not be serialized.
+    Serialize(serde_json::Error),
+    /// The message could not be sent.
+    Send(Error),
+}
+impl fmt::Display for WebSocketSendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialize(error) => write!(f, "Failed to serialize message. - {error}"),
+            Self::Send(error) => write!(f, "Failed to send message. - {error}"),
+        }
+    }
+}
+impl std::error::Error for WebSocketSendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serialize(error) => Some(error),
+            Self::Send(error) => Some(error),
+        }
+    }
+}
+impl From<serde_json::Error> for WebSocketSendError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialize(error)
+    }
+}
+impl From<Error> for WebSocketSendError {
+    fn from(error: Error) -> Self {
+        Self::Send(error)
+    }
+}
+
+impl WebSocketWriter<'_> {
+    /// Send `value` serialized as JSON text message to the conversation.
+    ///
+    /// Requires the `serde_json` feature.
+    #[allow(clippy::result_large_err)]
+    pub fn send_json<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), WebSocketSendError> {
+        let json = serde_json::to_string(value)?;
+        Ok(self.send_message(json)?)
+    }
+}
+
+impl WebSocketClients {
+    /// Send `value` serialized as JSON text message to a client.
+    ///
+    /// Returns [`Error::AlreadyClosed`] if a client with the specified [`WebSocketPeer`] does not exist.
+    ///
+    /// Requires the `serde_json` feature.
+    #[allow(clippy::result_large_err)]
+    pub fn write_json<T: Serialize + ?Sized>(
+        &mut self,
+        target: &WebSocketPeer,
+        value: &T,
+    ) -> Result<(), WebSocketSendError> {
+        self.write(target)
+            .ok_or(Error::AlreadyClosed)?
+            .send_json(value)
+    }
+
+    /// Send `value` serialized as JSON text message to all clients.
+    ///
+    /// `value` is serialized once and the message is shared by all clients.
+    /// Returns the clients the message could not be sent to. Like in
+    /// [`WebSocketClients::write_all_with_stats`], messages that have been queued because the
+    /// socket couldn't take them yet are not failures.
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn send_score(mut clients: ResMut<WebSocketClients>) {
+    ///     match clients.write_broadcast_json(&[("red", 3), ("blue", 1)]) {
+    ///         Ok(failed) => {
+    ///             for (peer, error) in failed {
+    ///                 error!("Failed to send score to {peer}. - {error}");
+    ///             }
+    ///         }
+    ///         Err(error) => error!("Failed to serialize score. - {error}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn write_broadcast_json<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<Vec<(WebSocketPeer, Error)>, serde_json::Error> {
+        let json = Utf8Bytes::from(serde_json::to_string(value)?);
+
+        let mut failed = Vec::new();
+        self.for_each_mut(|peer, writer| {
+            if let Err(error) = writer.send_message(json.clone()) {
+                failed.push((*peer, error));
+            }
+        });
+        Ok(failed)
+    }
+
+    /// Send `value` serialized as JSON text message to the clients in `targets`.
+    ///
+    /// `value` is serialized once and the message is shared by all clients.
+    /// Returns the clients the message could not be sent to, [`Error::AlreadyClosed`] for clients
+    /// that don't exist. Messages that have been queued because the socket couldn't take them yet
+    /// are not failures, see [`WebSocketWriter`].
+    ///
+    /// Requires the `serde_json` feature.
+    pub fn write_multicast_json<T: Serialize + ?Sized>(
+        &mut self,
+        targets: &[WebSocketPeer],
+        value: &T,
+    ) -> Result<Vec<(WebSocketPeer, Error)>, serde_json::Error> {
+        let json = Utf8Bytes::from(serde_json::to_string(value)?);
+
+        let failed = targets
+            .iter()
+            .filter_map(|peer| {
+                let result = match self.write(peer) {
+                    Some(mut writer) => writer.send_message(json.clone()),
+                    None => Err(Error::AlreadyClosed),
+                };
+                result.err().map(|error| (*peer, error))
+            })
+            .collect();
+        Ok(failed)
+    }
+}
@@ -0,0 +1,72 @@
+//! Ping clients on a fixed interval to keep idle conversations open.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use tungstenite::Bytes;
+
+use crate::{
+    client::{WebSocketClientMode, WebSocketClients},
+    writer::WebSocketWriter,
+    WebSocketPlugin,
+};
+
+/// Configuration of [`WebSocketKeepalivePlugin`].
+#[derive(Resource, Debug, Clone)]
+pub struct WebSocketKeepaliveConfig {
+    /// Time between two pings.
+    ///
+    /// The interval is fixed when the plugin is added, changing the resource afterwards has no
+    /// effect.
+    pub interval: Duration,
+}
+impl Default for WebSocketKeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// This plugin sends a ping to all clients in [`WebSocketClientMode::Parsed`] every
+/// [`WebSocketKeepaliveConfig::interval`].
+///
+/// The pings run on a timer instead of every frame. Their round-trip time is available with
+/// [`WebSocketClients::rtt`].
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// App::new().add_plugins((
+///     MinimalPlugins,
+///     WebSocketPlugin,
+///     WebSocketKeepalivePlugin(WebSocketKeepaliveConfig {
+///         interval: Duration::from_secs(10),
+///     }),
+/// ));
+/// ```
+#[derive(Default)]
+pub struct WebSocketKeepalivePlugin(pub WebSocketKeepaliveConfig);
+impl Plugin for WebSocketKeepalivePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WebSocketPlugin>() {
+            panic!("WebSocketPlugin is required for WebSocketKeepalivePlugin");
+        }
+
+        app.insert_resource(self.0.clone())
+            .add_systems(Update, ping_clients.run_if(on_timer(self.0.interval)));
+    }
+}
+
+fn ping_clients(mut clients: ResMut<WebSocketClients>) {
+    for (peer, client) in clients.inner.iter_mut() {
+        if client.mode != WebSocketClientMode::Parsed {
+            continue;
+        }
+
+        if let Err(error) = (WebSocketWriter { client }).send_ping(Bytes::new()) {
+            warn!("Failed to ping {peer}. - {error}");
+        }
+    }
+}
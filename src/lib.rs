@@ -1,25 +1,75 @@
 #![warn(clippy::unwrap_used)]
 #![doc = include_str!("../README.md")]
 
+pub mod backpressure;
+#[cfg(feature = "cbor")]
+pub mod cbor;
 pub mod client;
+pub mod close_codes;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod connector;
 pub mod events;
+pub mod frame_limit;
+pub mod group;
+pub mod handler;
+#[cfg(feature = "serde_json")]
+pub mod json;
+pub mod keepalive;
+pub mod middleware;
+pub mod pause;
 pub mod peer;
+pub mod reconnect;
+#[cfg(feature = "replication")]
+pub mod replication;
+#[cfg(feature = "secure")]
+pub mod secure;
 pub mod server;
+pub mod shard;
+#[cfg(feature = "serde_json")]
+pub mod state;
+pub mod streaming;
 pub mod writer;
 
 pub mod prelude {
+    pub use crate::backpressure::*;
+    #[cfg(feature = "cbor")]
+    pub use crate::cbor::*;
     pub use crate::client::*;
+    #[cfg(feature = "compression")]
+    pub use crate::compression::*;
+    pub use crate::connector::*;
     pub use crate::events::*;
+    pub use crate::handler::*;
+    #[cfg(feature = "serde_json")]
+    pub use crate::json::*;
+    pub use crate::keepalive::*;
+    pub use crate::middleware::*;
+    pub use crate::pause::*;
     pub use crate::peer::*;
+    pub use crate::reconnect::*;
+    #[cfg(feature = "replication")]
+    pub use crate::replication::*;
+    #[cfg(feature = "secure")]
+    pub use crate::secure::*;
     pub use crate::server::*;
+    #[cfg(feature = "serde_json")]
+    pub use crate::state::*;
+    pub use crate::streaming::*;
     pub use crate::writer::*;
     pub use crate::WebSocketPlugin;
     pub use crate::WebSocketServerPlugin;
 }
 
-use bevy::prelude::*;
+use bevy::{
+    ecs::schedule::{InternedScheduleLabel, ScheduleLabel, SystemConfigs},
+    prelude::*,
+};
 use client::*;
 use events::*;
+use middleware::WebSocketPluginBuilder;
+use pause::PausedWebSocketPlugin;
+use reconnect::*;
 use server::*;
 
 pub use tungstenite;
@@ -29,13 +79,61 @@ pub struct WebSocketPlugin;
 impl Plugin for WebSocketPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<WebSocketClients>()
+            .init_resource::<WebSocketErrorStrategy>()
             .add_event::<WebSocketMessageEvent>()
             .add_event::<WebSocketBinaryEvent>()
             .add_event::<WebSocketPongEvent>()
             .add_event::<WebSocketRawEvent>()
             .add_event::<WebSocketOpenEvent>()
             .add_event::<WebSocketCloseEvent>()
-            .add_systems(Update, handle_clients);
+            .add_event::<WebSocketReconnectEvent>()
+            .add_event::<WebSocketReconnectFailedEvent>()
+            .add_event::<WebSocketFrameLimitExceededEvent>()
+            .add_event::<WebSocketModeChangeEvent>()
+            .add_event::<WebSocketStreamChunkEvent>()
+            .add_systems(
+                PostUpdate,
+                cleanup_clients
+                    .run_if(has_disconnects)
+                    .in_set(WebSocketCleanupSet),
+            )
+            .insert_resource(MessageSchedule(Update.intern()))
+            .add_systems(Update, handle_messages_in(Update.intern()))
+            .init_resource::<WebSocketClientsSnapshot>()
+            .register_type::<WebSocketClientsSnapshot>()
+            .add_systems(Last, flush_clients.run_if(has_clients))
+            .add_systems(
+                Last,
+                update_snapshot.run_if(resource_changed::<WebSocketClients>),
+            );
+
+        #[cfg(feature = "secure")]
+        app.add_event::<WebSocketSecuredEvent>();
+    }
+}
+
+/// Read from the clients in `schedule` while it is the [`MessageSchedule`].
+///
+/// The server plugin is added after [`WebSocketPlugin`] and might move message handling into
+/// another schedule, these systems don't run in the previous one anymore then.
+pub(crate) fn handle_messages_in(schedule: InternedScheduleLabel) -> SystemConfigs {
+    (
+        handle_clients.run_if(has_clients),
+        handle_reconnect.run_if(has_reconnects),
+    )
+        .chain()
+        .run_if(move |current: Res<MessageSchedule>| current.0 == schedule)
+}
+
+impl WebSocketPlugin {
+    /// Build the plugin with middleware, see [`WebSocketPluginBuilder`].
+    pub fn builder() -> WebSocketPluginBuilder {
+        WebSocketPluginBuilder::default()
+    }
+
+    /// Hold received messages while the app is in `state`, see [`PausedWebSocketPlugin`].
+    pub fn pause_during<S: States>(state: S) -> PausedWebSocketPlugin<S> {
+        PausedWebSocketPlugin { state }
     }
 }
 
@@ -43,19 +141,68 @@ impl Plugin for WebSocketPlugin {
 pub struct WebSocketServerPlugin;
 impl Plugin for WebSocketServerPlugin {
     fn build(&self, app: &mut App) {
-        install_websocket_server(app, WebSocketServerConfig::default());
+        install_websocket_server(
+            app,
+            WebSocketServerConfig::default(),
+            Update.intern(),
+            Update.intern(),
+        );
     }
 }
 impl WebSocketServerPlugin {
     /// Customize the plugin with a [`WebSocketServerConfig`]
     pub fn custom(config: WebSocketServerConfig) -> CustomWebSocketServerPlugin {
-        CustomWebSocketServerPlugin(config)
+        CustomWebSocketServerPlugin {
+            config,
+            accept_schedule: Update.intern(),
+            message_schedule: Update.intern(),
+        }
+    }
+
+    /// Accept new connections in `accept_schedule` and process messages in `message_schedule`.
+    ///
+    /// ```no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// App::new().add_plugins((
+    ///     MinimalPlugins,
+    ///     WebSocketPlugin,
+    ///     WebSocketServerPlugin::custom_schedules(First, Update),
+    /// ));
+    /// ```
+    pub fn custom_schedules(
+        accept_schedule: impl ScheduleLabel,
+        message_schedule: impl ScheduleLabel,
+    ) -> CustomWebSocketServerPlugin {
+        Self::custom(WebSocketServerConfig::default())
+            .custom_schedules(accept_schedule, message_schedule)
     }
 }
 
-pub struct CustomWebSocketServerPlugin(WebSocketServerConfig);
+pub struct CustomWebSocketServerPlugin {
+    config: WebSocketServerConfig,
+    accept_schedule: InternedScheduleLabel,
+    message_schedule: InternedScheduleLabel,
+}
 impl Plugin for CustomWebSocketServerPlugin {
     fn build(&self, app: &mut App) {
-        install_websocket_server(app, self.0.clone());
+        install_websocket_server(
+            app,
+            self.config.clone(),
+            self.accept_schedule,
+            self.message_schedule,
+        );
+    }
+}
+impl CustomWebSocketServerPlugin {
+    /// Accept new connections in `accept_schedule` and process messages in `message_schedule`.
+    pub fn custom_schedules(
+        mut self,
+        accept_schedule: impl ScheduleLabel,
+        message_schedule: impl ScheduleLabel,
+    ) -> Self {
+        self.accept_schedule = accept_schedule.intern();
+        self.message_schedule = message_schedule.intern();
+        self
     }
 }
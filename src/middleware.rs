@@ -0,0 +1,86 @@
+//! Transform or drop received text messages before they are sent as events.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::{events::WebSocketMessageEvent, WebSocketPlugin};
+
+/// A function applied to every [`WebSocketMessageEvent`] before it is sent.
+///
+/// Returning `false` drops the event.
+pub type WebSocketMiddleware = Box<dyn Fn(&mut WebSocketMessageEvent) -> bool + Send + Sync>;
+
+/// Middleware added with [`WebSocketPluginBuilder::middleware`].
+#[derive(Resource, Clone, Default)]
+pub(crate) struct WebSocketMiddlewares(Arc<Vec<WebSocketMiddleware>>);
+impl WebSocketMiddlewares {
+    /// Run all middleware on `event` in the order they have been added.
+    ///
+    /// Returns false if one of them dropped the event, the following ones are skipped.
+    pub fn apply(&self, event: &mut WebSocketMessageEvent) -> bool {
+        self.0.iter().all(|middleware| middleware(event))
+    }
+}
+
+/// Build a [`WebSocketPlugin`] with middleware, see [`WebSocketPlugin::builder`].
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// fn trim(event: &mut WebSocketMessageEvent) -> bool {
+///     event.data = event.data.trim().to_string().into();
+///     true
+/// }
+///
+/// fn drop_empty(event: &mut WebSocketMessageEvent) -> bool {
+///     !event.data.is_empty()
+/// }
+///
+/// App::new().add_plugins((
+///     MinimalPlugins,
+///     WebSocketPlugin::builder()
+///         .middleware(trim)
+///         .middleware(drop_empty)
+///         .build(),
+/// ));
+/// ```
+#[derive(Default)]
+pub struct WebSocketPluginBuilder {
+    middleware: Vec<WebSocketMiddleware>,
+}
+impl WebSocketPluginBuilder {
+    /// Add a function that is applied to every [`WebSocketMessageEvent`] before it is sent.
+    ///
+    /// Middleware runs in the order it has been added. Returning `false` drops the event and
+    /// skips the following middleware.
+    pub fn middleware(
+        mut self,
+        middleware: impl Fn(&mut WebSocketMessageEvent) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Finish the plugin.
+    pub fn build(self) -> MiddlewareWebSocketPlugin {
+        MiddlewareWebSocketPlugin {
+            middleware: WebSocketMiddlewares(Arc::new(self.middleware)),
+        }
+    }
+}
+
+/// This plugin adds a [`WebSocketPlugin`] that applies middleware to received text messages,
+/// see [`WebSocketPluginBuilder`].
+pub struct MiddlewareWebSocketPlugin {
+    middleware: WebSocketMiddlewares,
+}
+impl Plugin for MiddlewareWebSocketPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WebSocketPlugin>() {
+            app.add_plugins(WebSocketPlugin);
+        }
+
+        app.insert_resource(self.middleware.clone());
+    }
+}
@@ -0,0 +1,122 @@
+//! Hold received messages while the app is in a [`State`].
+
+use bevy::prelude::*;
+
+use crate::{events::*, WebSocketPlugin};
+
+/// A message received while message processing was paused.
+#[derive(Debug)]
+pub(crate) enum HeldMessage {
+    Message(WebSocketMessageEvent),
+    Binary(WebSocketBinaryEvent),
+    Raw(WebSocketRawEvent),
+    StreamChunk(WebSocketStreamChunkEvent),
+}
+
+macro_rules! impl_from_event {
+    ($variant:ident, $t:ty) => {
+        impl From<$t> for HeldMessage {
+            fn from(event: $t) -> Self {
+                Self::$variant(event)
+            }
+        }
+    };
+}
+impl_from_event!(Message, WebSocketMessageEvent);
+impl_from_event!(Binary, WebSocketBinaryEvent);
+impl_from_event!(Raw, WebSocketRawEvent);
+impl_from_event!(StreamChunk, WebSocketStreamChunkEvent);
+
+#[derive(Resource, Debug, Default)]
+pub(crate) struct WebSocketPause {
+    paused: bool,
+    held: Vec<HeldMessage>,
+}
+
+/// Hold `event` if message processing is paused.
+///
+/// Returns the event if it should be sent right away.
+pub(crate) fn hold<E: Into<HeldMessage>>(
+    pause: &mut Option<ResMut<WebSocketPause>>,
+    event: E,
+) -> Option<E> {
+    match pause {
+        Some(pause) if pause.paused => {
+            pause.held.push(event.into());
+            None
+        }
+        _ => Some(event),
+    }
+}
+
+/// This plugin adds a [`WebSocketPlugin`] that holds received messages while the app is in a
+/// [`State`].
+///
+/// [`WebSocketMessageEvent`], [`WebSocketBinaryEvent`], [`WebSocketRawEvent`] and
+/// [`WebSocketStreamChunkEvent`] are kept in a queue and sent once the state is exited.
+/// Conversations are still processed while paused, pings are answered and closed conversations
+/// are reported.
+///
+/// ```no_run
+/// # use bevy::{prelude::*, state::app::StatesPlugin};
+/// # use bevy_websocket::prelude::*;
+/// #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+/// enum GameState {
+///     #[default]
+///     Loading,
+///     Playing,
+/// }
+///
+/// App::new()
+///     .add_plugins((
+///         MinimalPlugins,
+///         StatesPlugin,
+///         WebSocketPlugin::pause_during(GameState::Loading),
+///     ))
+///     .init_state::<GameState>();
+/// ```
+pub struct PausedWebSocketPlugin<S: States> {
+    pub(crate) state: S,
+}
+impl<S: States> Plugin for PausedWebSocketPlugin<S> {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WebSocketPlugin>() {
+            app.add_plugins(WebSocketPlugin);
+        }
+
+        app.init_resource::<WebSocketPause>()
+            .add_systems(OnEnter(self.state.clone()), pause)
+            .add_systems(OnExit(self.state.clone()), resume);
+    }
+}
+
+fn pause(mut pause: ResMut<WebSocketPause>) {
+    pause.paused = true;
+}
+
+fn resume(
+    mut pause: ResMut<WebSocketPause>,
+    mut message_w: EventWriter<WebSocketMessageEvent>,
+    mut binary_w: EventWriter<WebSocketBinaryEvent>,
+    mut raw_w: EventWriter<WebSocketRawEvent>,
+    mut chunk_w: EventWriter<WebSocketStreamChunkEvent>,
+) {
+    pause.paused = false;
+
+    for message in pause.held.drain(..) {
+        match message {
+            HeldMessage::Message(event) => {
+                message_w.send(event);
+            }
+            HeldMessage::Binary(event) => {
+                binary_w.send(event);
+            }
+            HeldMessage::Raw(event) => {
+                raw_w.send(event);
+            }
+            HeldMessage::StreamChunk(event) => {
+                chunk_w.send(event);
+            }
+        }
+    }
+}
@@ -9,14 +9,17 @@ use bevy::prelude::*;
 use tungstenite::stream::MaybeTlsStream;
 
 use crate::{
-    client::{WebSocketClientMode, WebSocketClients},
+    client::{tcp_stream, WebSocketClientMode, WebSocketClients},
     writer::WebSocketWriter,
 };
 
 /// Used to identify clients in [`WebSocketClients`].
 ///
-/// Wraps a [SocketAddr].
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Deref, DerefMut)]
+/// Wraps a [SocketAddr], which is only meaningful while the conversation is open. Components
+/// holding a peer should be excluded from saved scenes, e.g. with
+/// [`DynamicSceneBuilder::deny_component`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Deref, DerefMut, Reflect)]
+#[reflect(opaque, Debug, PartialEq, Hash)]
 pub struct WebSocketPeer(pub SocketAddr);
 impl WebSocketPeer {
     /// Create a [`WebSocketWriter`] for the client corresponding to this [`WebSocketPeer`].
@@ -40,15 +43,7 @@ impl WebSocketPeer {
     pub(crate) fn from_maybe_tls_stream(
         stream: &MaybeTlsStream<TcpStream>,
     ) -> Result<Self, io::Error> {
-        Ok(Self(match stream {
-            MaybeTlsStream::Plain(stream) => stream.peer_addr()?,
-            #[cfg(feature = "rustls")]
-            MaybeTlsStream::Rustls(stream) => stream.sock.peer_addr()?,
-            #[cfg(feature = "native-tls")]
-            MaybeTlsStream::NativeTls(stream) => stream.get_ref().peer_addr()?,
-            // because `MaybeTlsStream` implements #[non_exhaustive] we need to implement a &_ case.
-            _ => unreachable!("This should not happen."),
-        }))
+        Ok(Self(tcp_stream(stream).peer_addr()?))
     }
 }
 impl FromStr for WebSocketPeer {
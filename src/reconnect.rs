@@ -0,0 +1,189 @@
+//! Reconnect client conversations after they have been closed.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, TryRecvError};
+use tungstenite::{ClientRequestBuilder, Error};
+
+use crate::{
+    client::{WebSocketClientMode, WebSocketClients},
+    connector::{connect_in_background, Connected},
+    events::{WebSocketCloseEvent, WebSocketReconnectEvent, WebSocketReconnectFailedEvent},
+    peer::WebSocketPeer,
+};
+
+/// Backoff parameters used to reconnect a conversation.
+///
+/// The first attempt is made after `initial_delay`, every failed attempt multiplies the delay by
+/// `multiplier` up to `max_delay`.
+#[derive(Debug, Clone)]
+pub struct WebSocketReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f32,
+    /// Give up after this many failed attempts. [None] retries forever.
+    pub max_attempts: Option<u32>,
+}
+impl Default for WebSocketReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_attempts: None,
+        }
+    }
+}
+impl WebSocketReconnectPolicy {
+    fn delay(&self, attempts: u32) -> Duration {
+        let delay = self
+            .initial_delay
+            .mul_f32(self.multiplier.max(1.0).powi(attempts as i32));
+
+        delay.min(self.max_delay)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Reconnect {
+    request: ClientRequestBuilder,
+    mode: WebSocketClientMode,
+    policy: WebSocketReconnectPolicy,
+    attempts: u32,
+    /// Time of the next attempt, if the conversation has been closed.
+    next_attempt: Option<Instant>,
+    /// The attempt that is currently connecting on a separate thread.
+    pending: Option<Receiver<Connected>>,
+}
+
+impl WebSocketClients {
+    /// Reconnect a client using `request` whenever its conversation is closed.
+    ///
+    /// The conversation is reopened in the mode it is using right now and a
+    /// [`WebSocketReconnectEvent`] is sent once it has been reestablished. Attempts connect on a
+    /// separate thread, so they don't block the schedule.
+    /// Conversations closed by [`WebSocketClients::disconnect`] or [`WebSocketClients::retain`]
+    /// are not reconnected.
+    /// Returns [None] if a client with the specified [`WebSocketPeer`] does not exist.
+    pub fn reconnect_on_close(
+        &mut self,
+        target: &WebSocketPeer,
+        request: ClientRequestBuilder,
+        policy: WebSocketReconnectPolicy,
+    ) -> Option<()> {
+        let mode = self.inner.get(target)?.mode;
+
+        self.reconnect.insert(
+            *target,
+            Reconnect {
+                request,
+                mode,
+                policy,
+                attempts: 0,
+                next_attempt: None,
+                pending: None,
+            },
+        );
+        Some(())
+    }
+
+    /// Stop reconnecting a client.
+    ///
+    /// Returns [None] if the client has not been registered with
+    /// [`WebSocketClients::reconnect_on_close`].
+    pub fn cancel_reconnect(&mut self, target: &WebSocketPeer) -> Option<()> {
+        self.reconnect.swap_remove(target).map(|_| ())
+    }
+}
+
+/// Run condition that skips reconnecting while no client has been registered.
+pub(crate) fn has_reconnects(clients: Res<WebSocketClients>) -> bool {
+    !clients.reconnect.is_empty()
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn handle_reconnect(
+    mut clients: ResMut<WebSocketClients>,
+    mut close_r: EventReader<WebSocketCloseEvent>,
+    mut reconnect_w: EventWriter<WebSocketReconnectEvent>,
+    mut failed_w: EventWriter<WebSocketReconnectFailedEvent>,
+) {
+    let now = Instant::now();
+
+    for event in close_r.read() {
+        if let Some(reconnect) = clients.reconnect.get_mut(&event.peer) {
+            if reconnect.pending.is_none() {
+                reconnect.next_attempt = Some(now + reconnect.policy.initial_delay);
+            }
+        }
+    }
+
+    for reconnect in clients.reconnect.values_mut() {
+        if reconnect.next_attempt.is_some_and(|time| time <= now) {
+            reconnect.next_attempt = None;
+            reconnect.pending = Some(connect_in_background(
+                reconnect.request.clone(),
+                reconnect.mode,
+            ));
+        }
+    }
+
+    let finished: Vec<(WebSocketPeer, Connected)> = clients
+        .reconnect
+        .iter_mut()
+        .filter_map(|(peer, reconnect)| {
+            let result = match reconnect.pending.as_ref()?.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => Err(Error::ConnectionClosed),
+            };
+            reconnect.pending = None;
+            Some((*peer, result))
+        })
+        .collect();
+
+    for (old, result) in finished {
+        let Some(mut reconnect) = clients.reconnect.swap_remove(&old) else {
+            continue;
+        };
+
+        match result.and_then(|(new, client)| {
+            clients.insert_connected(new, client)?;
+            Ok(new)
+        }) {
+            Ok(new) => {
+                info!("Reconnected to: {}", new);
+
+                reconnect.attempts = 0;
+                clients.reconnect.insert(new, reconnect);
+
+                reconnect_w.send(WebSocketReconnectEvent { old, new });
+            }
+            Err(error) => {
+                reconnect.attempts += 1;
+
+                if reconnect
+                    .policy
+                    .max_attempts
+                    .is_some_and(|max| reconnect.attempts >= max)
+                {
+                    error!("Giving up to reconnect to {old}. - {error}");
+
+                    failed_w.send(WebSocketReconnectFailedEvent {
+                        peer: old,
+                        attempts: reconnect.attempts,
+                        error: error.to_string(),
+                    });
+                    continue;
+                }
+
+                let delay = reconnect.policy.delay(reconnect.attempts);
+                warn!("Failed to reconnect to {old}, retrying in {delay:?}. - {error}");
+
+                reconnect.next_attempt = Some(now + delay);
+                clients.reconnect.insert(old, reconnect);
+            }
+        }
+    }
+}
@@ -0,0 +1,315 @@
+//! Mirror components to connected clients.
+//!
+//! Components registered with [`WebSocketReplicationAppExt::replicate`] are sent as JSON to all
+//! [`Parsed`] conversations whenever they are added, changed or removed on entities marked with
+//! [`Replicated`].
+//!
+//! # Wire format
+//!
+//! Entities are identified by a stable id, which is the bit representation of the [`Entity`]
+//! formatted as a decimal string. Components are keyed by their short type name.
+//! All messages are text messages.
+//!
+//! New conversations receive a snapshot of all replicated entities first:
+//!
+//! ```json
+//! { "type": "snapshot", "entities": { "4294967296": { "Position": { "x": 0.0, "y": 1.0 } } } }
+//! ```
+//!
+//! A snapshot is sent again if an update could not be sent to a conversation or
+//! [`ReplicationInterest`] has been changed. Clients should replace all replicated entities when
+//! they receive one.
+//!
+//! Afterwards changes are batched into at most one delta per frame:
+//!
+//! ```json
+//! {
+//!     "type": "delta",
+//!     "changed": { "4294967296": { "Position": { "x": 0.5, "y": 1.0 } } },
+//!     "removed": { "4294967297": ["Position"] }
+//! }
+//! ```
+//!
+//! Entities that have been despawned, or are no longer marked with [`Replicated`], are announced
+//! once per frame:
+//!
+//! ```json
+//! { "type": "despawn", "entities": ["4294967296"] }
+//! ```
+//!
+//! [`Parsed`]: crate::client::WebSocketClientMode::Parsed
+
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::prelude::*;
+use disqualified::ShortName;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    client::{WebSocketClientMode, WebSocketClients},
+    peer::WebSocketPeer,
+    server::WebSocketServerConfig,
+    WebSocketPlugin,
+};
+
+/// Marks an entity whose replicated components are sent to clients.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Replicated;
+
+/// Limits which entities are replicated to which conversations.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// App::new().insert_resource(ReplicationInterest::new(|_peer, entity| {
+///     // only replicate every other entity
+///     entity.index() % 2 == 0
+/// }));
+/// ```
+#[derive(Resource)]
+#[allow(clippy::type_complexity)]
+pub struct ReplicationInterest(Box<dyn Fn(&WebSocketPeer, Entity) -> bool + Send + Sync>);
+impl ReplicationInterest {
+    pub fn new(filter: impl Fn(&WebSocketPeer, Entity) -> bool + Send + Sync + 'static) -> Self {
+        Self(Box::new(filter))
+    }
+}
+
+/// Systems that collect and send replicated components. They run in [`PostUpdate`].
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReplicationSet {
+    Collect,
+    Send,
+}
+
+type Components = Map<String, Value>;
+
+#[derive(Resource, Default)]
+struct ReplicationState {
+    /// The last replicated state of every entity.
+    cache: BTreeMap<u64, Components>,
+    changed: BTreeMap<u64, Components>,
+    removed: BTreeMap<u64, Vec<String>>,
+    despawned: Vec<u64>,
+    /// Conversations that have received a snapshot.
+    synced: HashSet<WebSocketPeer>,
+}
+
+/// This plugin will replicate registered components to all clients.
+pub struct WebSocketReplicationPlugin;
+impl Plugin for WebSocketReplicationPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WebSocketPlugin>() {
+            panic!("WebSocketPlugin is required for WebSocketReplicationPlugin");
+        }
+
+        let mut state = ReplicationState::default();
+        if let Some(expected_connections) = app
+            .world()
+            .get_resource::<WebSocketServerConfig>()
+            .and_then(|config| config.expected_connections)
+        {
+            state.synced.reserve(expected_connections);
+        }
+
+        app.insert_resource(state)
+            .configure_sets(
+                PostUpdate,
+                (ReplicationSet::Collect, ReplicationSet::Send).chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    collect_despawned.in_set(ReplicationSet::Collect),
+                    send_replication.in_set(ReplicationSet::Send),
+                ),
+            );
+    }
+}
+
+pub trait WebSocketReplicationAppExt {
+    /// Replicate the component `C` of all entities marked with [`Replicated`].
+    ///
+    /// Requires [`WebSocketReplicationPlugin`].
+    fn replicate<C: Component + Serialize>(&mut self) -> &mut Self;
+}
+impl WebSocketReplicationAppExt for App {
+    fn replicate<C: Component + Serialize>(&mut self) -> &mut Self {
+        if !self.is_plugin_added::<WebSocketReplicationPlugin>() {
+            panic!("WebSocketReplicationPlugin is required to replicate components");
+        }
+
+        self.add_systems(
+            PostUpdate,
+            collect_component::<C>
+                .in_set(ReplicationSet::Collect)
+                .before(collect_despawned),
+        )
+    }
+}
+
+fn collect_component<C: Component + Serialize>(
+    query: Query<(Entity, Ref<C>, Ref<Replicated>)>,
+    mut removed: RemovedComponents<C>,
+    mut state: ResMut<ReplicationState>,
+) {
+    let name = ShortName::of::<C>().to_string();
+
+    for (entity, component, marker) in query.iter() {
+        if !component.is_changed() && !marker.is_added() {
+            continue;
+        }
+
+        match serde_json::to_value(&*component) {
+            Ok(value) => {
+                let id = entity.to_bits();
+
+                if let Some(removed) = state.removed.get_mut(&id) {
+                    removed.retain(|removed| *removed != name);
+                }
+                state
+                    .cache
+                    .entry(id)
+                    .or_default()
+                    .insert(name.clone(), value.clone());
+                state
+                    .changed
+                    .entry(id)
+                    .or_default()
+                    .insert(name.clone(), value);
+            }
+            Err(error) => error!("Failed to serialize {name} of {entity}. - {error}"),
+        }
+    }
+
+    for entity in removed.read() {
+        let id = entity.to_bits();
+
+        let Some(components) = state.cache.get_mut(&id) else {
+            continue;
+        };
+        if components.remove(&name).is_none() {
+            continue;
+        }
+
+        if let Some(changed) = state.changed.get_mut(&id) {
+            changed.remove(&name);
+        }
+        state.removed.entry(id).or_default().push(name.clone());
+    }
+}
+
+fn collect_despawned(
+    mut removed: RemovedComponents<Replicated>,
+    marked: Query<(), With<Replicated>>,
+    mut state: ResMut<ReplicationState>,
+) {
+    for entity in removed.read() {
+        // the marker might have been removed and inserted again in the same frame
+        if marked.contains(entity) {
+            continue;
+        }
+
+        let id = entity.to_bits();
+        if state.cache.remove(&id).is_some() {
+            state.changed.remove(&id);
+            state.removed.remove(&id);
+            state.despawned.push(id);
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn send_replication(
+    mut clients: ResMut<WebSocketClients>,
+    mut state: ResMut<ReplicationState>,
+    interest: Option<Res<ReplicationInterest>>,
+) {
+    let state = &mut *state;
+    let changed = std::mem::take(&mut state.changed);
+    let removed = std::mem::take(&mut state.removed);
+    let despawned = std::mem::take(&mut state.despawned);
+
+    state.synced.retain(|peer| clients.inner.contains_key(peer));
+    // a new filter changes which entities peers know about, so they need a fresh snapshot
+    if interest
+        .as_ref()
+        .is_some_and(|interest| interest.is_changed())
+    {
+        state.synced.clear();
+    }
+
+    let peers: Vec<WebSocketPeer> = clients
+        .inner
+        .iter()
+        .filter(|(_, client)| client.mode == WebSocketClientMode::Parsed)
+        .map(|(peer, _)| *peer)
+        .collect();
+
+    let filter = |peer: &WebSocketPeer, id: &u64| {
+        interest
+            .as_ref()
+            .is_none_or(|interest| (interest.0)(peer, Entity::from_bits(*id)))
+    };
+
+    for peer in peers {
+        let mut messages = Vec::new();
+
+        let snapshot = !state.synced.contains(&peer);
+        if snapshot {
+            let entities: Map<String, Value> = state
+                .cache
+                .iter()
+                .filter(|(id, _)| filter(&peer, id))
+                .map(|(id, components)| (id.to_string(), Value::Object(components.clone())))
+                .collect();
+
+            messages.push(json!({ "type": "snapshot", "entities": entities }));
+        } else {
+            let changed: Map<String, Value> = changed
+                .iter()
+                .filter(|(id, components)| !components.is_empty() && filter(&peer, id))
+                .map(|(id, components)| (id.to_string(), Value::Object(components.clone())))
+                .collect();
+            let removed: Map<String, Value> = removed
+                .iter()
+                .filter(|(id, components)| !components.is_empty() && filter(&peer, id))
+                .map(|(id, components)| (id.to_string(), json!(components)))
+                .collect();
+
+            if !changed.is_empty() || !removed.is_empty() {
+                messages.push(json!({ "type": "delta", "changed": changed, "removed": removed }));
+            }
+
+            let despawned: Vec<String> = despawned
+                .iter()
+                .filter(|id| filter(&peer, id))
+                .map(|id| id.to_string())
+                .collect();
+
+            if !despawned.is_empty() {
+                messages.push(json!({ "type": "despawn", "entities": despawned }));
+            }
+        }
+
+        let Some(mut writer) = clients.write(&peer) else {
+            continue;
+        };
+        let result = messages
+            .into_iter()
+            .try_for_each(|message| writer.send_message(message.to_string()));
+
+        match result {
+            Ok(()) if snapshot => {
+                state.synced.insert(peer);
+            }
+            Ok(()) => (),
+            Err(error) => {
+                error!("Failed to replicate to {peer}. - {error}");
+                // the peer missed an update, start over with a snapshot
+                state.synced.remove(&peer);
+            }
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Noise based application-layer encryption and mutual authentication.
+//!
+//! This is meant for deployments that can't terminate TLS but still need confidentiality and
+//! mutual authentication. Once [`WebSocketSecurePlugin`] is added, every [`Parsed`] conversation
+//! starts with a Noise handshake and [`WebSocketWriter::send_message`] /
+//! [`WebSocketMessageEvent`] keep working with plaintext on the API level.
+//! Raw conversations are not affected.
+//!
+//! # Framing
+//!
+//! Every Noise message is carried by exactly one binary WebSocket message.
+//!
+//! 1. After the WebSocket connection has been opened the connecting side (initiator) sends the
+//!    first handshake message. Both sides then exchange handshake messages until the handshake
+//!    pattern is complete. Handshake messages carry an empty payload.
+//! 2. Afterwards every binary WebSocket message contains a single Noise transport message.
+//!    The decrypted payload starts with one byte describing its content:
+//!    - `0x01`: the remaining bytes are a UTF-8 encoded text message.
+//!    - `0x02`: the remaining bytes are a binary message.
+//!
+//! The used protocols are `Noise_XX_25519_ChaChaPoly_BLAKE2s` and
+//! `Noise_KK_25519_ChaChaPoly_BLAKE2s`. A Noise message can not exceed 65535 bytes, which leaves
+//! 65518 bytes for the payload of a single message.
+//!
+//! [`WebSocketOpenEvent`] is sent before the handshake has been completed. Messages sent until
+//! then, e.g. replicated state for a new conversation, are held back and sent encrypted once the
+//! handshake has been completed and [`WebSocketSecuredEvent`] is sent.
+//!
+//! Text messages, or binary messages that aren't valid Noise messages, close the connection.
+//! A failed handshake is closed with [`SECURE_HANDSHAKE_FAILED`], a failed transport message
+//! with [`CloseCode::Policy`].
+//!
+//! [`Parsed`]: WebSocketClientMode::Parsed
+//! [`WebSocketWriter::send_message`]: crate::writer::WebSocketWriter::send_message
+//! [`WebSocketMessageEvent`]: crate::events::WebSocketMessageEvent
+//! [`WebSocketOpenEvent`]: crate::events::WebSocketOpenEvent
+//! [`WebSocketSecuredEvent`]: crate::events::WebSocketSecuredEvent
+
+use std::{fmt::Debug, io};
+
+use bevy::prelude::*;
+use snow::{params::NoiseParams, Builder, HandshakeState, Keypair, TransportState};
+use tungstenite::{
+    protocol::{frame::coding::CloseCode, CloseFrame},
+    Bytes, Error, Message,
+};
+
+use crate::{
+    client::{Client, WebSocketClientMode, WebSocketClients},
+    WebSocketPlugin,
+};
+
+/// Close code used when the secure handshake fails.
+pub const SECURE_HANDSHAKE_FAILED: u16 = 4001;
+
+const NOISE_XX: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+const NOISE_KK: &str = "Noise_KK_25519_ChaChaPoly_BLAKE2s";
+
+const MAX_MESSAGE_LEN: usize = 65535;
+const TAG_LEN: usize = 16;
+
+const TEXT: u8 = 0x01;
+const BINARY: u8 = 0x02;
+
+/// The Noise handshake pattern used to establish a secure session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketSecurePattern {
+    /// Both sides transmit their static keys during the handshake.
+    ///
+    /// The remote static key can be verified on [`WebSocketSecuredEvent`].
+    ///
+    /// [`WebSocketSecuredEvent`]: crate::events::WebSocketSecuredEvent
+    XX,
+
+    /// Both sides know the static key of the other side beforehand.
+    KK { remote_public_key: Vec<u8> },
+}
+
+/// Configuration for [`WebSocketSecurePlugin`].
+#[derive(Clone)]
+pub struct WebSocketSecureConfig {
+    /// The local static private key. (Curve25519)
+    pub private_key: Vec<u8>,
+
+    /// Handshake pattern used for all conversations.
+    pub pattern: WebSocketSecurePattern,
+}
+impl WebSocketSecureConfig {
+    /// Generate a new static key pair which can be used with this config.
+    pub fn generate_keypair() -> Result<Keypair, snow::Error> {
+        Builder::new(noise_params(NOISE_XX)).generate_keypair()
+    }
+
+    fn builder(&self) -> Builder<'_> {
+        match &self.pattern {
+            WebSocketSecurePattern::XX => {
+                Builder::new(noise_params(NOISE_XX)).local_private_key(&self.private_key)
+            }
+            WebSocketSecurePattern::KK { remote_public_key } => {
+                Builder::new(noise_params(NOISE_KK))
+                    .local_private_key(&self.private_key)
+                    .remote_public_key(remote_public_key)
+            }
+        }
+    }
+}
+impl Debug for WebSocketSecureConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSocketSecureConfig")
+            .field("pattern", &self.pattern)
+            .finish_non_exhaustive()
+    }
+}
+
+fn noise_params(pattern: &str) -> NoiseParams {
+    pattern.parse().expect("Failed to parse noise params.")
+}
+
+/// This plugin will encrypt and authenticate all parsed conversations.
+///
+/// Secured conversations are never streamed, see [`WebSocketServerConfig::streaming`].
+/// Requires the `secure` feature.
+///
+/// [`WebSocketServerConfig::streaming`]: crate::server::WebSocketServerConfig::streaming
+pub struct WebSocketSecurePlugin(pub WebSocketSecureConfig);
+impl Plugin for WebSocketSecurePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WebSocketPlugin>() {
+            panic!("WebSocketPlugin is required for WebSocketSecurePlugin");
+        }
+
+        let mut clients = app.world_mut().resource_mut::<WebSocketClients>();
+        clients.secure = Some(self.0.clone());
+
+        if clients.streaming.is_some() {
+            warn!("Streaming is disabled for secured conversations.");
+        }
+    }
+}
+
+pub(crate) enum SecureSession {
+    Handshake(Box<HandshakeState>),
+    Transport(Box<TransportState>),
+    Failed,
+}
+impl Debug for SecureSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Handshake(_) => f.write_str("Handshake"),
+            Self::Transport(_) => f.write_str("Transport"),
+            Self::Failed => f.write_str("Failed"),
+        }
+    }
+}
+
+/// Result of an inbound message of a secured conversation.
+pub(crate) enum SecureInbound {
+    /// The message has been decrypted or didn't need decryption.
+    Message(Message),
+    /// The handshake has been completed, contains the remote static key.
+    Secured(Vec<u8>),
+    /// The message was part of the handshake.
+    Pending,
+}
+
+impl SecureSession {
+    /// Continue the handshake with an incoming message.
+    ///
+    /// Returns the message that has to be sent to the remote, if any.
+    fn read_handshake(&mut self, message: &[u8]) -> Result<Option<Vec<u8>>, snow::Error> {
+        let Self::Handshake(handshake) = self else {
+            return Err(snow::Error::State(
+                snow::error::StateProblem::HandshakeAlreadyFinished,
+            ));
+        };
+
+        let mut buf = vec![0; MAX_MESSAGE_LEN];
+        handshake.read_message(message, &mut buf)?;
+
+        let reply = if !handshake.is_handshake_finished() && handshake.is_my_turn() {
+            let len = handshake.write_message(&[], &mut buf)?;
+            buf.truncate(len);
+            Some(buf)
+        } else {
+            None
+        };
+
+        self.try_finish()?;
+        Ok(reply)
+    }
+
+    /// Start the handshake as initiator.
+    fn write_handshake(&mut self) -> Result<Vec<u8>, snow::Error> {
+        let Self::Handshake(handshake) = self else {
+            return Err(snow::Error::State(
+                snow::error::StateProblem::HandshakeAlreadyFinished,
+            ));
+        };
+
+        let mut buf = vec![0; MAX_MESSAGE_LEN];
+        let len = handshake.write_message(&[], &mut buf)?;
+        buf.truncate(len);
+
+        Ok(buf)
+    }
+
+    fn try_finish(&mut self) -> Result<(), snow::Error> {
+        if matches!(self, Self::Handshake(handshake) if handshake.is_handshake_finished()) {
+            if let Self::Handshake(handshake) = std::mem::replace(self, Self::Failed) {
+                *self = Self::Transport(Box::new(handshake.into_transport_mode()?));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remote_static(&self) -> Option<&[u8]> {
+        match self {
+            Self::Handshake(handshake) => handshake.get_remote_static(),
+            Self::Transport(transport) => transport.get_remote_static(),
+            Self::Failed => None,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn encrypt_text(&mut self, data: &[u8]) -> Result<Bytes, Error> {
+        self.encrypt(TEXT, data)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn encrypt_binary(&mut self, data: &[u8]) -> Result<Bytes, Error> {
+        self.encrypt(BINARY, data)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn encrypt(&mut self, kind: u8, data: &[u8]) -> Result<Bytes, Error> {
+        let Self::Transport(transport) = self else {
+            return Err(io_error("Secure session has not been established."));
+        };
+
+        let mut payload = Vec::with_capacity(data.len() + 1);
+        payload.push(kind);
+        payload.extend_from_slice(data);
+
+        let mut buf = vec![0; payload.len() + TAG_LEN];
+        let len = transport
+            .write_message(&payload, &mut buf)
+            .map_err(|error| io_error(error.to_string()))?;
+        buf.truncate(len);
+
+        Ok(buf.into())
+    }
+
+    fn decrypt(&mut self, message: &[u8]) -> Result<Message, snow::Error> {
+        let Self::Transport(transport) = self else {
+            return Err(snow::Error::State(
+                snow::error::StateProblem::HandshakeNotFinished,
+            ));
+        };
+
+        let mut buf = vec![0; message.len()];
+        let len = transport.read_message(message, &mut buf)?;
+        buf.truncate(len);
+
+        match buf.split_first() {
+            Some((&TEXT, data)) => String::from_utf8(data.to_vec())
+                .map(|text| Message::Text(text.into()))
+                .map_err(|_| snow::Error::Decrypt),
+            Some((&BINARY, data)) => Ok(Message::Binary(Bytes::copy_from_slice(data))),
+            _ => Err(snow::Error::Decrypt),
+        }
+    }
+}
+
+fn io_error(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    Error::Io(io::Error::other(error))
+}
+
+impl WebSocketClients {
+    /// Attach a secure session to a client if [`WebSocketSecurePlugin`] was added.
+    ///
+    /// The initiator sends the first handshake message immediately.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn start_secure_session(
+        &self,
+        client: &mut Client,
+        initiator: bool,
+    ) -> Result<(), Error> {
+        let Some(config) = &self.secure else {
+            return Ok(());
+        };
+
+        if client.mode != WebSocketClientMode::Parsed {
+            return Ok(());
+        }
+
+        let builder = config.builder();
+        let handshake = if initiator {
+            builder.build_initiator()
+        } else {
+            builder.build_responder()
+        }
+        .map_err(|error| io_error(error.to_string()))?;
+
+        let mut session = SecureSession::Handshake(Box::new(handshake));
+        if initiator {
+            let message = session
+                .write_handshake()
+                .map_err(|error| io_error(error.to_string()))?;
+            client.send_now(Message::Binary(message.into()))?;
+        }
+
+        client.secure = Some(session);
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Encrypt and send a text or binary message of a secured conversation.
+    ///
+    /// Messages sent before the handshake has been completed are held back and sent once it is.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn send_secure(&mut self, message: Message) -> Result<(), Error> {
+        let Some(session) = &mut self.secure else {
+            return self.send(message);
+        };
+        if matches!(session, SecureSession::Handshake(_)) {
+            self.secure_backlog.push(message);
+            return Ok(());
+        }
+
+        let data = match &message {
+            Message::Text(data) => session.encrypt_text(data.as_bytes())?,
+            Message::Binary(data) => session.encrypt_binary(data)?,
+            _ => return self.send(message),
+        };
+        self.send(Message::Binary(data))
+    }
+
+    /// Pass an inbound message through the secure session of this client.
+    ///
+    /// Messages of clients without a secure session are returned untouched.
+    /// If the session fails the conversation is closed and the close frame is returned as error.
+    pub(crate) fn secure_inbound(&mut self, message: Message) -> Result<SecureInbound, CloseFrame> {
+        let Some(session) = &mut self.secure else {
+            return Ok(SecureInbound::Message(message));
+        };
+
+        let result = match (&message, &*session) {
+            (Message::Binary(data), SecureSession::Handshake(_)) => {
+                match session.read_handshake(data) {
+                    Ok(reply) => {
+                        let inbound = match session {
+                            SecureSession::Transport(_) => Ok(SecureInbound::Secured(
+                                session.remote_static().unwrap_or_default().to_vec(),
+                            )),
+                            _ => Ok(SecureInbound::Pending),
+                        };
+
+                        if let Some(reply) = reply {
+                            if let Err(error) = self.send_now(Message::Binary(reply.into())) {
+                                error!("Failed to send secure handshake. - {error}");
+                            }
+                        }
+
+                        if matches!(inbound, Ok(SecureInbound::Secured(_))) {
+                            for message in std::mem::take(&mut self.secure_backlog) {
+                                if let Err(error) = self.send_secure(message) {
+                                    error!("Failed to send held back message. - {error}");
+                                }
+                            }
+                        }
+                        inbound
+                    }
+                    Err(_) => Err(CloseCode::Library(SECURE_HANDSHAKE_FAILED)),
+                }
+            }
+            (Message::Binary(data), SecureSession::Transport(_)) => session
+                .decrypt(data)
+                .map(SecureInbound::Message)
+                .map_err(|_| CloseCode::Policy),
+            (Message::Text(_), SecureSession::Transport(_)) => Err(CloseCode::Policy),
+            (Message::Text(_) | Message::Binary(_), _) => {
+                Err(CloseCode::Library(SECURE_HANDSHAKE_FAILED))
+            }
+            _ => Ok(SecureInbound::Message(message)),
+        };
+
+        result.map_err(|code| {
+            self.secure = Some(SecureSession::Failed);
+
+            let frame = CloseFrame {
+                code,
+                reason: "Secure session failed.".into(),
+            };
+            if self.stream.close(Some(frame.clone())).is_err() {
+                error!("Failed to close secure session.");
+            }
+
+            frame
+        })
+    }
+}
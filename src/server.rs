@@ -1,24 +1,28 @@
-use std::collections::VecDeque;
-use std::mem::MaybeUninit;
-use std::sync::Arc;
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{
     io,
-    net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream},
 };
 
-use bevy::log::LogPlugin;
+use bevy::ecs::schedule::InternedScheduleLabel;
 use bevy::prelude::*;
-use parking_lot::Mutex;
-use tungstenite::accept_hdr;
+use crossbeam_channel::{Receiver, Sender, TryRecvError, TrySendError};
 use tungstenite::handshake::server::{ErrorResponse, Request, Response};
 use tungstenite::http::{HeaderMap, HeaderValue, StatusCode};
+use tungstenite::protocol::WebSocketConfig;
 use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{accept_hdr_with_config, WebSocket};
 
-use crate::client::{Client, WebSocketClientMode, WebSocketClients};
+use crate::client::{tcp_stream, Client, RemovalOrder, WebSocketClientMode, WebSocketClients};
 use crate::peer::WebSocketPeer;
-use crate::{events::*, WebSocketPlugin};
+use crate::streaming::WebSocketStreamConfig;
+use crate::writer::FlushStrategy;
+use crate::{events::*, handle_messages_in, WebSocketPlugin};
 
 #[derive(Resource, Clone)]
 pub struct WebSocketServerConfig {
@@ -31,6 +35,78 @@ pub struct WebSocketServerConfig {
 
     /// Protocol used for raw conversations.
     pub raw_protocol: String,
+
+    /// Maximum number of accepted connections waiting for their handshake.
+    ///
+    /// Connections accepted while the queue is full are rejected with
+    /// [`WebSocketServerConfig::queue_full_status`]. The same number of completed handshakes can
+    /// wait to be registered in [`WebSocketClients`]. Must not be `0`.
+    pub request_queue_size: usize,
+
+    /// Status of the response sent to connections rejected because the request queue is full.
+    ///
+    /// [None] closes them without a response.
+    pub queue_full_status: Option<StatusCode>,
+
+    /// Number of threads performing the handshakes of accepted connections.
+    ///
+    /// Handshakes never block the listener or the schedule. The server only accepts plain
+    /// `ws://` connections, `wss://` has to be terminated by a proxy in front of it.
+    pub handshake_threads: usize,
+
+    /// Time a handshake thread waits for the request of a connection before dropping it.
+    ///
+    /// Must not be zero, sockets can't wait for no time at all.
+    pub handshake_timeout: Duration,
+
+    /// Number of conversations to reserve memory for when the server starts.
+    pub expected_connections: Option<usize>,
+
+    /// Configuration of accepted conversations.
+    ///
+    /// [`WebSocketConfig::read_buffer_size`] is allocated once per conversation and reused for
+    /// every message, a smaller buffer lowers the memory usage of many idle conversations.
+    ///
+    /// Every conversation keeps the configuration it has been accepted with, changing this
+    /// resource doesn't affect established conversations. This includes the
+    /// [`WebSocketConfig::max_frame_size`] of frames read in [`WebSocketClientMode::Raw`].
+    ///
+    /// Writes never block, so messages a peer doesn't read stay in the write buffer of its
+    /// conversation. [`WebSocketConfig::max_write_buffer_size`] defaults to
+    /// [`MAX_WRITE_BUFFER_SIZE`] instead of tungstenite's unlimited size, so a peer that stops
+    /// reading can't grow the memory of the server without bound. Once the buffer is full, sends
+    /// to that conversation fail with [`tungstenite::Error::WriteBufferFull`].
+    pub websocket_config: WebSocketConfig,
+
+    /// When messages are sent to the network, see [`FlushStrategy`].
+    pub flush_strategy: FlushStrategy,
+
+    /// Receive large messages of all conversations in chunks, see [`WebSocketStreamConfig`].
+    ///
+    /// Streaming is disabled for conversations secured by `WebSocketSecurePlugin`, as their
+    /// messages can only be decrypted as a whole. A warning is logged if both are configured.
+    pub streaming: Option<WebSocketStreamConfig>,
+
+    /// How closed conversations are removed from [`WebSocketClients`], see [`RemovalOrder`].
+    pub removal_order: RemovalOrder,
+
+    /// Disable Nagle's algorithm on accepted connections.
+    ///
+    /// Small messages are sent immediately instead of being delayed to be combined with following
+    /// ones, which lowers the latency of frequent updates.
+    pub tcp_nodelay: bool,
+
+    /// Time spent accepting queued connections per frame.
+    ///
+    /// Connections that don't fit into the budget are accepted in the following frames, at least
+    /// one connection is accepted per frame. See [`WebSocketStats::accept_backlog_frames`].
+    pub accept_budget: Duration,
+
+    /// Number of workers all clients are polled by, see [`WebSocketClients::set_workers`].
+    ///
+    /// The default of `1` reads one client per frame on the thread of the schedule, which is
+    /// enough for small games. Use more workers for thousands of connections.
+    pub workers: usize,
 }
 impl Default for WebSocketServerConfig {
     fn default() -> Self {
@@ -38,50 +114,283 @@ impl Default for WebSocketServerConfig {
             addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0)),
             parsed_protocol: "bevy_websocket".to_string(),
             raw_protocol: "bevy_websocket_raw".to_string(),
+            request_queue_size: 128,
+            queue_full_status: Some(StatusCode::SERVICE_UNAVAILABLE),
+            handshake_threads: 2,
+            handshake_timeout: Duration::from_secs(5),
+            expected_connections: None,
+            websocket_config: WebSocketConfig::default()
+                .max_write_buffer_size(MAX_WRITE_BUFFER_SIZE),
+            flush_strategy: FlushStrategy::default(),
+            streaming: None,
+            removal_order: RemovalOrder::default(),
+            tcp_nodelay: true,
+            accept_budget: Duration::from_millis(1),
+            workers: 1,
+        }
+    }
+}
+
+/// Default [`WebSocketConfig::max_write_buffer_size`] of accepted conversations and of
+/// conversations with servers.
+pub const MAX_WRITE_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+impl WebSocketServerConfig {
+    /// Check that the configuration can be used to run a server.
+    ///
+    /// [`WebSocketServerPlugin`](crate::WebSocketServerPlugin) panics with the returned error
+    /// when it is added, instead of failing once the first client connects.
+    pub fn validate(&self) -> Result<(), WebSocketConfigValidationError> {
+        for protocol in [&self.parsed_protocol, &self.raw_protocol] {
+            // requested protocols are split at commas and trimmed, so these could never match
+            let unmatchable = protocol.is_empty()
+                || protocol.contains(|c: char| c == ',' || c.is_ascii_whitespace());
+            if unmatchable || HeaderValue::from_str(protocol).is_err() {
+                return Err(WebSocketConfigValidationError::InvalidProtocolName(
+                    protocol.clone(),
+                ));
+            }
+        }
+
+        if self.request_queue_size == 0 {
+            return Err(WebSocketConfigValidationError::ZeroRequestQueueSize);
+        }
+        if self.handshake_timeout.is_zero() {
+            return Err(WebSocketConfigValidationError::ZeroHandshakeTimeout);
+        }
+        Ok(())
+    }
+}
+
+/// Error of [`WebSocketServerConfig::validate`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum WebSocketConfigValidationError {
+    /// A protocol is empty, contains commas or whitespace, or is not a valid header value.
+    InvalidProtocolName(String),
+    /// [`WebSocketServerConfig::request_queue_size`] is `0`, which would hand each connection
+    /// over only while the server waits for it.
+    ZeroRequestQueueSize,
+    /// [`WebSocketServerConfig::handshake_timeout`] is zero, which can't be set on a socket.
+    ZeroHandshakeTimeout,
+}
+impl fmt::Display for WebSocketConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidProtocolName(protocol) => {
+                write!(f, "Invalid protocol name {protocol:?}.")
+            }
+            Self::ZeroRequestQueueSize => write!(f, "The request queue size must not be 0."),
+            Self::ZeroHandshakeTimeout => write!(f, "The handshake timeout must not be zero."),
+        }
+    }
+}
+impl std::error::Error for WebSocketConfigValidationError {}
+
+/// Schedule the client messages are processed in.
+///
+/// [`WebSocketPlugin`] processes them in [`Update`] until the server plugin sets another one.
+#[derive(Resource)]
+pub(crate) struct MessageSchedule(pub InternedScheduleLabel);
+
+#[derive(Default)]
+struct ServerState {
+    stopped: AtomicBool,
+    local_addr: OnceLock<SocketAddr>,
+    pending_handshakes: AtomicUsize,
+    connections_accepted: AtomicU64,
+}
+
+/// Control the listener of the server.
+///
+/// The listener thread blocks while waiting for connections, so it doesn't wake up while idle.
+#[derive(Resource, Clone, Default)]
+pub struct WebSocketServerControl(Arc<ServerState>);
+impl WebSocketServerControl {
+    /// Stop accepting new connections and close the listener.
+    ///
+    /// Established conversations are kept open.
+    pub fn stop(&self) {
+        if self.0.stopped.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        // wake the listener up, it is blocked waiting for the next connection
+        if let Some(addr) = self.local_addr() {
+            let addr = match addr.ip() {
+                IpAddr::V4(ip) if ip.is_unspecified() => {
+                    SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port())
+                }
+                IpAddr::V6(ip) if ip.is_unspecified() => {
+                    SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port())
+                }
+                _ => addr,
+            };
+
+            if let Err(error) = TcpStream::connect(addr) {
+                error!("Failed to wake up the websocket server. - {error}");
+            }
+        }
+    }
+
+    /// Returns true if [`WebSocketServerControl::stop`] has been called.
+    pub fn is_stopped(&self) -> bool {
+        self.0.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Get the address the server is listening on.
+    ///
+    /// Returns [None] until the server has been started or if it failed to start.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.0.local_addr.get().copied()
+    }
+
+    /// Get the number of accepted connections waiting for a handshake thread.
+    pub fn handshake_queue_len(&self) -> usize {
+        self.0.pending_handshakes.load(Ordering::Relaxed)
+    }
+}
+
+/// Counters of the server.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct WebSocketStats {
+    /// Number of frames it took to accept the last backlog of queued connections.
+    pub accept_backlog_frames: u32,
+    /// Number of frames the current backlog has been accepted for.
+    pub pending_backlog_frames: u32,
+    /// Number of completed handshakes.
+    pub handshakes: u64,
+    /// Number of connections ever accepted by the listener.
+    ///
+    /// Connections are counted as soon as they are accepted, including those rejected because
+    /// the request queue was full and those whose handshake failed. The counter only increases,
+    /// so sampling it twice gives the rate of incoming connections.
+    pub total_connections_accepted: u64,
+    /// Total time spent performing the completed handshakes.
+    pub handshake_time: Duration,
+    /// Number of messages sent with [`WebSocketClients::write_with_deadline`] that have been
+    /// dropped because they missed their deadline.
+    pub deadline_drops: u64,
+}
+impl WebSocketStats {
+    /// Get the average time a handshake took.
+    pub fn average_handshake_time(&self) -> Duration {
+        match self.handshakes {
+            0 => Duration::ZERO,
+            handshakes => self.handshake_time.div_f64(handshakes as f64),
         }
     }
 }
 
-type RequestQueueInner = Arc<Mutex<VecDeque<MaybeTlsStream<TcpStream>>>>;
+/// A connection that completed its handshake on a handshake thread.
+struct Handshake {
+    peer: WebSocketPeer,
+    stream: WebSocket<MaybeTlsStream<TcpStream>>,
+    mode: WebSocketClientMode,
+    headers: HeaderMap<HeaderValue>,
+    duration: Duration,
+}
+
+#[derive(Resource, Deref)]
+struct RequestQueue(Receiver<Handshake>);
 
-#[derive(Resource, Default, Deref)]
-struct RequestQueue(RequestQueueInner);
+/// Receives the address of the listener once it has been started.
+#[derive(Resource, Deref)]
+struct ServerReady(Receiver<SocketAddr>);
 
-pub(crate) fn install_websocket_server(app: &mut App, config: WebSocketServerConfig) -> &mut App {
+pub(crate) fn install_websocket_server(
+    app: &mut App,
+    config: WebSocketServerConfig,
+    accept_schedule: InternedScheduleLabel,
+    message_schedule: InternedScheduleLabel,
+) -> &mut App {
     if !app.is_plugin_added::<WebSocketPlugin>() {
-        const ERROR: &str = "WebSocketPlugin is required for WebSocketServerPlugin";
+        panic!("WebSocketPlugin is required for WebSocketServerPlugin");
+    }
+    if let Err(error) = config.validate() {
+        panic!("Invalid WebSocketServerConfig. - {error}");
+    }
 
-        if app.is_plugin_added::<LogPlugin>() {
-            error!("{ERROR}");
-            return app;
-        } else {
-            panic!("{ERROR}");
+    let mut schedule = app.world_mut().resource_mut::<MessageSchedule>();
+    if schedule.0 != message_schedule {
+        schedule.0 = message_schedule;
+        app.add_systems(message_schedule, handle_messages_in(message_schedule));
+    }
+
+    {
+        let mut clients = app.world_mut().resource_mut::<WebSocketClients>();
+        clients.set_flush_strategy(config.flush_strategy);
+        clients.streaming = config.streaming;
+        clients.set_removal_order(config.removal_order);
+        clients.set_workers(config.workers);
+
+        #[cfg(feature = "secure")]
+        if clients.streaming.is_some() && clients.secure.is_some() {
+            warn!("Streaming is disabled for secured conversations.");
+        }
+
+        if let Some(expected_connections) = config.expected_connections {
+            clients.reserve(expected_connections);
         }
     }
 
-    let queue = RequestQueue::default();
+    let (request_s, request_r) = crossbeam_channel::bounded(config.request_queue_size);
+    let (sender, receiver) = crossbeam_channel::bounded(config.request_queue_size);
+    let (ready_s, ready_r) = crossbeam_channel::bounded(1);
+    let control = WebSocketServerControl::default();
+
+    for _ in 0..config.handshake_threads.max(1) {
+        let config = config.clone();
+        let request_r = request_r.clone();
+        let sender = sender.clone();
+        let control = control.clone();
+
+        thread::spawn(move || handshake_worker(config, request_r, sender, control));
+    }
 
     {
-        let queue = queue.clone();
         let config = config.clone();
+        let control = control.clone();
 
-        thread::spawn(move || listen(config, queue));
+        thread::spawn(move || listen(config, request_s, ready_s, control));
     }
 
     app.insert_resource(config)
-        .insert_resource(queue)
-        .add_systems(Update, handle_request)
+        .insert_resource(control)
+        .insert_resource(RequestQueue(receiver))
+        .insert_resource(ServerReady(ready_r))
+        .add_event::<WebSocketServerReadyEvent>()
+        .init_resource::<WebSocketStats>()
+        .add_systems(
+            accept_schedule,
+            (
+                handle_ready.run_if(resource_exists::<ServerReady>),
+                handle_request.run_if(has_requests),
+                update_stats,
+            ),
+        )
 }
 
 fn start_server(config: WebSocketServerConfig) -> Result<TcpListener, io::Error> {
     let server = TcpListener::bind(config.addr)?;
     info!("Server running at ws://{}", server.local_addr()?);
-    server.set_nonblocking(true)?;
 
     Ok(server)
 }
 
-fn listen(config: WebSocketServerConfig, queue: RequestQueueInner) {
+fn listen(
+    config: WebSocketServerConfig,
+    queue: Sender<TcpStream>,
+    ready: Sender<SocketAddr>,
+    control: WebSocketServerControl,
+) {
+    let tcp_nodelay = config.tcp_nodelay;
+    let queue_full_response = config.queue_full_status.map(|status| {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status.as_str(),
+            status.canonical_reason().unwrap_or_default()
+        )
+    });
     let server = match start_server(config) {
         Ok(server) => server,
         Err(error) => {
@@ -90,48 +399,144 @@ fn listen(config: WebSocketServerConfig, queue: RequestQueueInner) {
         }
     };
 
+    if let Ok(addr) = server.local_addr() {
+        let _ = control.0.local_addr.set(addr);
+        let _ = ready.try_send(addr);
+    }
+
+    // `stop` might have been called before the address was known
+    if control.is_stopped() {
+        return;
+    }
+
     for request in server.incoming() {
+        if control.is_stopped() {
+            info!("Stopped websocket server.");
+            return;
+        }
+
         match request {
-            Ok(req) => queue.lock_arc().push_back(MaybeTlsStream::Plain(req)),
-            Err(e) => {
-                if e.kind() == io::ErrorKind::WouldBlock {
-                    thread::sleep(Duration::from_millis(50));
+            Ok(req) => {
+                control
+                    .0
+                    .connections_accepted
+                    .fetch_add(1, Ordering::Relaxed);
+
+                if let Err(error) = req.set_nodelay(tcp_nodelay) {
+                    warn!("Failed to set TCP_NODELAY. - {error}");
                 }
+
+                control.0.pending_handshakes.fetch_add(1, Ordering::Relaxed);
+                match queue.try_send(req) {
+                    Ok(()) => (),
+                    Err(TrySendError::Full(mut req)) => {
+                        control.0.pending_handshakes.fetch_sub(1, Ordering::Relaxed);
+                        warn!("Request queue is full, rejecting connection.");
+
+                        if let Some(response) = &queue_full_response {
+                            let _ = req.write_all(response.as_bytes());
+                        }
+                    }
+                    // the app has been dropped
+                    Err(TrySendError::Disconnected(_)) => return,
+                }
+            }
+            Err(error) => {
+                error!("Failed to accept connection. - {error}");
+                thread::sleep(Duration::from_millis(50));
             }
         };
     }
 }
 
-fn handle_request_inner(
-    request_queue: Res<RequestQueue>,
-    mut clients: ResMut<WebSocketClients>,
-    config: Res<WebSocketServerConfig>,
-    mut open_w: EventWriter<WebSocketOpenEvent>,
-) -> Result<(), io::Error> {
-    if !request_queue.0.is_locked() {
-        let mut queue = request_queue.clone().lock_arc();
-        if let Some(request) = queue.pop_front() {
-            let peer = WebSocketPeer::from_maybe_tls_stream(&request)?;
-            let mut mode: MaybeUninit<WebSocketClientMode> = MaybeUninit::uninit();
-            let mut headers: MaybeUninit<HeaderMap<HeaderValue>> = MaybeUninit::uninit();
-
-            if let Ok(stream) = accept_hdr(request, |request: &Request, response: Response| {
-                handle_accept(request, response, &config, &mut mode, &mut headers)
-            }) {
-                info!("New connection from: {}", peer);
-
-                let (mode, headers) = unsafe { (mode.assume_init(), headers.assume_init()) };
-
-                clients.inner.insert(peer, Client { stream, mode });
-
-                open_w.send(WebSocketOpenEvent {
-                    peer,
-                    mode,
-                    headers,
-                });
+fn handshake_worker(
+    config: WebSocketServerConfig,
+    requests: Receiver<TcpStream>,
+    queue: Sender<Handshake>,
+    control: WebSocketServerControl,
+) {
+    for request in requests {
+        control.0.pending_handshakes.fetch_sub(1, Ordering::Relaxed);
+
+        match handshake(request, &config) {
+            Ok(Some(handshake)) => {
+                // the app has been dropped
+                if queue.send(handshake).is_err() {
+                    return;
+                }
             }
+            Ok(None) => (),
+            Err(error) => error!("Failed to get request. - {error}"),
         }
     }
+}
+
+#[allow(clippy::result_large_err)]
+fn handshake(
+    request: TcpStream,
+    config: &WebSocketServerConfig,
+) -> Result<Option<Handshake>, io::Error> {
+    let start = Instant::now();
+    request.set_read_timeout(Some(config.handshake_timeout))?;
+
+    let request = MaybeTlsStream::Plain(request);
+    let peer = WebSocketPeer::from_maybe_tls_stream(&request)?;
+    let mut mode = None;
+    let mut headers = None;
+
+    let Ok(stream) = accept_hdr_with_config(
+        request,
+        |request: &Request, response: Response| {
+            handle_accept(request, response, config, &mut mode, &mut headers)
+        },
+        Some(config.websocket_config),
+    ) else {
+        return Ok(None);
+    };
+    tcp_stream(stream.get_ref()).set_read_timeout(None)?;
+
+    let (Some(mode), Some(headers)) = (mode, headers) else {
+        return Err(io::Error::other("Handshake accepted without a protocol."));
+    };
+
+    Ok(Some(Handshake {
+        peer,
+        stream,
+        mode,
+        headers,
+        duration: start.elapsed(),
+    }))
+}
+
+fn handle_request_inner(
+    handshake: Handshake,
+    clients: &mut WebSocketClients,
+    open_w: &mut EventWriter<WebSocketOpenEvent>,
+) -> Result<(), io::Error> {
+    let Handshake {
+        peer,
+        stream,
+        mode,
+        headers,
+        ..
+    } = handshake;
+    info!("New connection from: {}", peer);
+
+    #[allow(unused_mut)]
+    let mut client = Client::new(stream, mode)?;
+    #[cfg(feature = "secure")]
+    if let Err(error) = clients.start_secure_session(&mut client, false) {
+        error!("Failed to start secure session. - {error}");
+        return Ok(());
+    }
+
+    clients.insert(peer, client);
+
+    open_w.send(WebSocketOpenEvent {
+        peer,
+        mode,
+        headers: Arc::new(headers),
+    });
 
     Ok(())
 }
@@ -141,10 +546,10 @@ fn handle_accept(
     request: &Request,
     mut response: Response,
     config: &WebSocketServerConfig,
-    mode: &mut MaybeUninit<WebSocketClientMode>,
-    headers: &mut MaybeUninit<HeaderMap<HeaderValue>>,
+    mode: &mut Option<WebSocketClientMode>,
+    headers: &mut Option<HeaderMap<HeaderValue>>,
 ) -> Result<Response, ErrorResponse> {
-    headers.write(request.headers().clone());
+    *headers = Some(request.headers().clone());
 
     if let Some(protocols) = request.headers().get("Sec-WebSocket-Protocol") {
         let protocols: Vec<&str> = protocols
@@ -155,7 +560,7 @@ fn handle_accept(
             .collect();
 
         if protocols.contains(&config.parsed_protocol.as_str()) {
-            mode.write(WebSocketClientMode::Parsed);
+            *mode = Some(WebSocketClientMode::Parsed);
 
             response.headers_mut().append(
                 "Sec-WebSocket-Protocol",
@@ -166,7 +571,7 @@ fn handle_accept(
             );
             Ok(response)
         } else if protocols.contains(&config.raw_protocol.as_str()) {
-            mode.write(WebSocketClientMode::Raw);
+            *mode = Some(WebSocketClientMode::Raw);
 
             response.headers_mut().append(
                 "Sec-WebSocket-Protocol",
@@ -191,13 +596,65 @@ fn handle_accept(
     }
 }
 
+fn handle_ready(
+    mut commands: Commands,
+    ready: Res<ServerReady>,
+    mut ready_w: EventWriter<WebSocketServerReadyEvent>,
+) {
+    match ready.try_recv() {
+        Ok(addr) => {
+            ready_w.send(WebSocketServerReadyEvent { addr });
+            commands.remove_resource::<ServerReady>();
+        }
+        Err(TryRecvError::Empty) => (),
+        // the server failed to start
+        Err(TryRecvError::Disconnected) => commands.remove_resource::<ServerReady>(),
+    }
+}
+
+fn update_stats(
+    control: Res<WebSocketServerControl>,
+    clients: Res<WebSocketClients>,
+    mut stats: ResMut<WebSocketStats>,
+) {
+    let connections_accepted = control.0.connections_accepted.load(Ordering::Relaxed);
+    if stats.total_connections_accepted != connections_accepted {
+        stats.total_connections_accepted = connections_accepted;
+    }
+    if stats.deadline_drops != clients.deadline_drops {
+        stats.deadline_drops = clients.deadline_drops;
+    }
+}
+
+/// Run condition that skips accepting while no connection is waiting.
+fn has_requests(request_queue: Res<RequestQueue>) -> bool {
+    !request_queue.is_empty()
+}
+
 fn handle_request(
     request_queue: Res<RequestQueue>,
-    clients: ResMut<WebSocketClients>,
+    mut clients: ResMut<WebSocketClients>,
     config: Res<WebSocketServerConfig>,
-    open_w: EventWriter<WebSocketOpenEvent>,
+    mut stats: ResMut<WebSocketStats>,
+    mut open_w: EventWriter<WebSocketOpenEvent>,
 ) {
-    if let Err(error) = handle_request_inner(request_queue, clients, config, open_w) {
-        error!("Failed to get request. - {error}");
+    let start = Instant::now();
+    stats.pending_backlog_frames += 1;
+
+    while let Ok(handshake) = request_queue.try_recv() {
+        stats.handshakes += 1;
+        stats.handshake_time += handshake.duration;
+
+        if let Err(error) = handle_request_inner(handshake, &mut clients, &mut open_w) {
+            error!("Failed to get request. - {error}");
+        }
+
+        if start.elapsed() >= config.accept_budget {
+            break;
+        }
+    }
+
+    if request_queue.is_empty() {
+        stats.accept_backlog_frames = std::mem::take(&mut stats.pending_backlog_frames);
     }
 }
@@ -0,0 +1,111 @@
+//! Poll clients on multiple threads for high connection counts.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Instant,
+};
+
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+
+use crate::{
+    client::{Client, Polled, WebSocketClients, WebSocketErrorStrategy},
+    peer::WebSocketPeer,
+};
+
+/// Get the shard a client belongs to.
+///
+/// Depends only on the peer, so a client is always polled and flushed by the same worker.
+fn shard_of(peer: &WebSocketPeer, workers: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    peer.hash(&mut hasher);
+    (hasher.finish() % workers as u64) as usize
+}
+
+impl WebSocketClients {
+    /// Get the number of workers clients are polled by, see [`WebSocketClients::set_workers`].
+    pub fn workers(&self) -> usize {
+        self.workers.max(1)
+    }
+
+    /// Set the number of workers clients are polled by.
+    ///
+    /// With a single worker, the default, one client is read per frame on the thread of the
+    /// schedule. With more workers the clients are split into that many shards by a hash of
+    /// their [`WebSocketPeer`]. Every frame each shard reads once from all of its clients and
+    /// flushes them on a thread of the [`ComputeTaskPool`]. The events are sent in the order of
+    /// the shards.
+    ///
+    /// Use more workers if there are too many clients to read each of them every few frames.
+    /// `0` is treated as `1`.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_websocket::prelude::*;
+    /// fn use_all_cores(mut clients: ResMut<WebSocketClients>) {
+    ///     let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    ///     clients.set_workers(cores);
+    /// }
+    /// ```
+    pub fn set_workers(&mut self, workers: usize) {
+        self.workers = workers;
+    }
+
+    /// Split the clients into [`WebSocketClients::workers`] shards.
+    fn shards(&mut self) -> Vec<Vec<(WebSocketPeer, &mut Client)>> {
+        let workers = self.workers();
+        let mut shards: Vec<Vec<_>> = (0..workers).map(|_| Vec::new()).collect();
+        for (peer, client) in self.inner.iter_mut() {
+            shards[shard_of(peer, workers)].push((*peer, client));
+        }
+        shards
+    }
+
+    /// Read once from every client, each shard on its own thread.
+    ///
+    /// Returns what has been received per shard.
+    pub(crate) fn poll_shards(
+        &mut self,
+        error_strategy: &WebSocketErrorStrategy,
+    ) -> Vec<Vec<(WebSocketPeer, Polled)>> {
+        let max_frames_per_second = self.max_frames_per_second;
+        let shards = self.shards();
+
+        ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+            for shard in shards {
+                scope.spawn(async move {
+                    shard
+                        .into_iter()
+                        .filter_map(|(peer, client)| {
+                            client
+                                .poll(peer, error_strategy, max_frames_per_second)
+                                .map(|polled| (peer, polled))
+                        })
+                        .collect()
+                });
+            }
+        })
+    }
+
+    /// Flush every client, each shard on its own thread.
+    ///
+    /// Returns the number of messages that missed their deadline.
+    pub(crate) fn flush_shards(&mut self) -> u64 {
+        let max_queued = self.max_queued.unwrap_or(usize::MAX);
+        let now = Instant::now();
+        let shards = self.shards();
+
+        ComputeTaskPool::get_or_init(TaskPool::default)
+            .scope(|scope| {
+                for shard in shards {
+                    scope.spawn(async move {
+                        shard
+                            .into_iter()
+                            .map(|(peer, client)| client.flush_frame(peer, max_queued, now))
+                            .sum::<u64>()
+                    });
+                }
+            })
+            .into_iter()
+            .sum()
+    }
+}
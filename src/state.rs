@@ -0,0 +1,102 @@
+//! Broadcast [`States`] to clients.
+//!
+//! Every transition of a broadcasted state is sent as a text message with the following envelope:
+//!
+//! ```json
+//! { "state": "GameState", "from": "Lobby", "to": "InGame" }
+//! ```
+//!
+//! `state` is the short type name of the state, `from` and `to` are the serialized states or
+//! `null` if there is none. Newly opened conversations receive the current state once with `from`
+//! set to `null`.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use disqualified::ShortName;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    client::{WebSocketClientMode, WebSocketClients},
+    events::WebSocketOpenEvent,
+    peer::WebSocketPeer,
+};
+
+pub trait WebSocketStateAppExt {
+    /// Send every transition of `S` to all [`Parsed`] conversations.
+    ///
+    /// [`Parsed`]: WebSocketClientMode::Parsed
+    fn broadcast_state<S: States + Serialize>(&mut self) -> &mut Self;
+
+    /// Send every transition of `S` to all conversations matching `filter`.
+    fn broadcast_state_filtered<S: States + Serialize>(
+        &mut self,
+        filter: impl Fn(&WebSocketPeer, WebSocketClientMode) -> bool + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+impl WebSocketStateAppExt for App {
+    fn broadcast_state<S: States + Serialize>(&mut self) -> &mut Self {
+        self.broadcast_state_filtered::<S>(|_, mode| mode == WebSocketClientMode::Parsed)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn broadcast_state_filtered<S: States + Serialize>(
+        &mut self,
+        filter: impl Fn(&WebSocketPeer, WebSocketClientMode) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.add_systems(
+            PostUpdate,
+            move |mut transitions: EventReader<StateTransitionEvent<S>>,
+                  mut open_r: EventReader<WebSocketOpenEvent>,
+                  state: Option<Res<State<S>>>,
+                  mut clients: ResMut<WebSocketClients>| {
+                // new conversations receive the current state instead of this frame's transitions
+                let opened: HashSet<WebSocketPeer> = open_r.read().map(|open| open.peer).collect();
+
+                let envelope = |from: Option<&S>, to: Option<&S>| {
+                    json!({
+                        "state": ShortName::of::<S>().to_string(),
+                        "from": from,
+                        "to": to,
+                    })
+                    .to_string()
+                };
+
+                let mut messages: Vec<(bool, String)> = transitions
+                    .read()
+                    .filter(|transition| transition.exited != transition.entered)
+                    .map(|transition| {
+                        let message =
+                            envelope(transition.exited.as_ref(), transition.entered.as_ref());
+                        (false, message)
+                    })
+                    .collect();
+
+                if let Some(state) = state.filter(|_| !opened.is_empty()) {
+                    messages.push((true, envelope(None, Some(state.get()))));
+                }
+
+                for (new, message) in messages {
+                    let peers: Vec<WebSocketPeer> = clients
+                        .inner
+                        .iter()
+                        .filter(|(peer, client)| {
+                            opened.contains(peer) == new && filter(peer, client.mode)
+                        })
+                        .map(|(peer, _)| *peer)
+                        .collect();
+
+                    for peer in peers {
+                        if let Some(Err(error)) = clients
+                            .write(&peer)
+                            .map(|mut writer| writer.send_message(message.as_str()))
+                        {
+                            error!("Failed to broadcast state to {peer}. - {error}");
+                        }
+                    }
+                }
+            },
+        )
+    }
+}
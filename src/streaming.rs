@@ -0,0 +1,407 @@
+//! Receive very large messages in chunks.
+//!
+//! Conversations with a [`WebSocketStreamConfig`] read frames themselves instead of letting
+//! tungstenite assemble whole messages. Messages up to [`WebSocketStreamConfig::threshold`] bytes
+//! are still sent as [`WebSocketMessageEvent`] or [`WebSocketBinaryEvent`]. Larger messages are
+//! sent as [`WebSocketStreamChunkEvent`]s while their bytes arrive, so they are never buffered as
+//! a whole.
+//!
+//! Messages exceeding [`WebSocketStreamConfig::max_size`] close the conversation with
+//! [`CloseCode::Size`] (1009).
+//!
+//! Conversations secured by `WebSocketSecurePlugin` are never streamed, as their messages can only
+//! be decrypted as a whole.
+//!
+//! [`WebSocketMessageEvent`]: crate::events::WebSocketMessageEvent
+//! [`WebSocketBinaryEvent`]: crate::events::WebSocketBinaryEvent
+//! [`WebSocketStreamChunkEvent`]: crate::events::WebSocketStreamChunkEvent
+
+use std::{
+    fmt,
+    io::{self, Cursor},
+};
+
+use bytes::{Buf, Bytes, BytesMut};
+use tungstenite::{
+    error::{CapacityError, ProtocolError},
+    protocol::{
+        frame::{
+            coding::{CloseCode, Control, Data, OpCode},
+            FrameHeader,
+        },
+        CloseFrame,
+    },
+    Error, Message, Utf8Bytes,
+};
+
+use crate::client::{Client, WebSocketClientMode, WebSocketClients};
+use crate::peer::WebSocketPeer;
+
+/// Limits of streamed messages.
+#[derive(Debug, Clone, Copy)]
+pub struct WebSocketStreamConfig {
+    /// Messages larger than this are streamed in chunks.
+    pub threshold: usize,
+    /// Maximum size of a streamed message.
+    pub max_size: usize,
+}
+impl Default for WebSocketStreamConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 1 << 20,
+            max_size: 1 << 30,
+        }
+    }
+}
+
+/// A message that is currently being received.
+#[derive(Debug)]
+struct StreamMessage {
+    opcode: Data,
+    size: usize,
+    /// Payload of all fragments received before the message started streaming.
+    buffer: BytesMut,
+    /// Set once the message is streamed.
+    stream_id: Option<u64>,
+    first: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct StreamState {
+    message: Option<StreamMessage>,
+    /// Payload bytes of the current frame that have been consumed.
+    offset: usize,
+    next_id: u64,
+}
+
+pub(crate) enum StreamInbound {
+    Message(Message),
+    Chunk {
+        stream_id: u64,
+        chunk: Bytes,
+        first: bool,
+        last: bool,
+    },
+}
+
+fn unmask(data: &mut [u8], mask: Option<[u8; 4]>, offset: usize) {
+    if let Some(mask) = mask {
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte ^= mask[(offset + i) % 4];
+        }
+    }
+}
+
+impl Client {
+    /// Returns true if messages of this client are read by [`Client::read_streaming`].
+    pub fn is_streaming(&self) -> bool {
+        #[cfg(feature = "secure")]
+        if self.secure.is_some() {
+            return false;
+        }
+
+        self.streaming.is_some()
+    }
+
+    /// Read the next message or chunk of a streaming client.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn read_streaming(&mut self) -> Result<Option<StreamInbound>, Error> {
+        let Some(config) = self.streaming else {
+            return Ok(None);
+        };
+        let read_buffer_size = self.stream.get_config().read_buffer_size;
+        if self.raw_buffer.capacity() == 0 {
+            self.raw_buffer.reserve(read_buffer_size);
+        }
+
+        loop {
+            if self.raw_header.is_none() {
+                let mut cursor = Cursor::new(&self.raw_buffer[..]);
+                if let Some((header, len)) = FrameHeader::parse(&mut cursor)? {
+                    let advanced = cursor.position() as usize;
+                    self.raw_buffer.advance(advanced);
+
+                    self.start_frame(&header, len as usize, &config)?;
+                    self.raw_header = Some((header, len));
+                }
+            }
+
+            if let Some((header, len)) = self.raw_header.take() {
+                let remaining = len as usize - self.stream_state.offset;
+                let streamed = self
+                    .stream_state
+                    .message
+                    .as_ref()
+                    .is_some_and(|message| message.stream_id.is_some());
+
+                if matches!(header.opcode, OpCode::Data(_)) && streamed {
+                    let size = remaining.min(self.raw_buffer.len());
+                    if size > 0 || remaining == 0 {
+                        return Ok(Some(self.read_chunk(header, len, size)));
+                    }
+                } else if remaining <= self.raw_buffer.len() {
+                    let mut payload = self.raw_buffer.split_to(remaining);
+                    unmask(&mut payload, header.mask, 0);
+
+                    if let Some(message) = self.finish_frame(&header, payload)? {
+                        return Ok(Some(StreamInbound::Message(message)));
+                    }
+                    continue;
+                }
+
+                self.raw_header = Some((header, len));
+                self.raw_buffer
+                    .reserve(remaining.clamp(2, read_buffer_size.max(2)));
+            } else {
+                // the largest possible header
+                self.raw_buffer.reserve(14);
+            }
+
+            match self.fill_raw_buffer() {
+                Ok(0) => return Err(Error::Protocol(ProtocolError::ResetWithoutClosingHandshake)),
+                Ok(_) => (),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(error) => return Err(Error::Io(error)),
+            }
+        }
+    }
+
+    /// Validate a new frame header and update the message it belongs to.
+    #[allow(clippy::result_large_err)]
+    fn start_frame(
+        &mut self,
+        header: &FrameHeader,
+        len: usize,
+        config: &WebSocketStreamConfig,
+    ) -> Result<(), Error> {
+        let state = &mut self.stream_state;
+        state.offset = 0;
+
+        if header.rsv1 || header.rsv2 || header.rsv3 {
+            return Err(Error::Protocol(ProtocolError::NonZeroReservedBits));
+        }
+
+        match header.opcode {
+            OpCode::Control(Control::Reserved(opcode)) => Err(Error::Protocol(
+                ProtocolError::UnknownControlFrameType(opcode),
+            )),
+            OpCode::Control(_) if !header.is_final => {
+                Err(Error::Protocol(ProtocolError::FragmentedControlFrame))
+            }
+            OpCode::Control(_) if len > 125 => {
+                Err(Error::Protocol(ProtocolError::ControlFrameTooBig))
+            }
+            OpCode::Control(_) => Ok(()),
+            OpCode::Data(Data::Reserved(opcode)) => {
+                Err(Error::Protocol(ProtocolError::UnknownDataFrameType(opcode)))
+            }
+            OpCode::Data(opcode) => {
+                let message = match (opcode, &mut state.message) {
+                    (Data::Continue, Some(message)) => message,
+                    (Data::Continue, None) => {
+                        return Err(Error::Protocol(ProtocolError::UnexpectedContinueFrame))
+                    }
+                    (_, Some(message)) => {
+                        return Err(Error::Protocol(ProtocolError::ExpectedFragment(
+                            message.opcode,
+                        )))
+                    }
+                    (opcode, message @ None) => message.insert(StreamMessage {
+                        opcode,
+                        size: 0,
+                        buffer: BytesMut::new(),
+                        stream_id: None,
+                        first: true,
+                    }),
+                };
+
+                message.size = message.size.saturating_add(len);
+                if message.size > config.max_size {
+                    return Err(Error::Capacity(CapacityError::MessageTooLong {
+                        size: message.size,
+                        max_size: config.max_size,
+                    }));
+                }
+
+                if message.stream_id.is_none() && message.size > config.threshold {
+                    message.stream_id = Some(state.next_id);
+                    state.next_id = state.next_id.wrapping_add(1);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Take `size` available payload bytes of the current frame of a streamed message.
+    fn read_chunk(&mut self, header: FrameHeader, len: u64, size: usize) -> StreamInbound {
+        let offset = self.stream_state.offset;
+        let mut data = self.raw_buffer.split_to(size);
+        unmask(&mut data, header.mask, offset);
+
+        let done = offset + size == len as usize;
+        let last = done && header.is_final;
+        if done {
+            self.stream_state.offset = 0;
+        } else {
+            self.stream_state.offset += size;
+            self.raw_header = Some((header, len));
+        }
+
+        let message = self.stream_state.message.as_mut();
+        let (stream_id, first, chunk) = match message {
+            Some(message) => {
+                let first = std::mem::replace(&mut message.first, false);
+                let chunk = if message.buffer.is_empty() {
+                    data.freeze()
+                } else {
+                    let mut buffer = std::mem::take(&mut message.buffer);
+                    buffer.extend_from_slice(&data);
+                    buffer.freeze()
+                };
+                (message.stream_id.unwrap_or_default(), first, chunk)
+            }
+            None => (0, true, data.freeze()),
+        };
+
+        if last {
+            self.stream_state.message = None;
+        }
+
+        StreamInbound::Chunk {
+            stream_id,
+            chunk,
+            first,
+            last,
+        }
+    }
+
+    /// Handle the complete payload of a frame that is not streamed.
+    #[allow(clippy::result_large_err)]
+    fn finish_frame(
+        &mut self,
+        header: &FrameHeader,
+        payload: BytesMut,
+    ) -> Result<Option<Message>, Error> {
+        let payload = match header.opcode {
+            OpCode::Control(Control::Ping) => return Ok(Some(Message::Ping(payload.freeze()))),
+            OpCode::Control(Control::Pong) => return Ok(Some(Message::Pong(payload.freeze()))),
+            OpCode::Control(_) => {
+                let frame = close_frame(payload.freeze())?;
+
+                // reply to the closing handshake, tungstenite doesn't see the close frame
+                let _ = self.stream.close(frame.clone());
+                return Ok(Some(Message::Close(frame)));
+            }
+            OpCode::Data(_) => payload,
+        };
+
+        if let Some(message) = &mut self.stream_state.message {
+            message.buffer.extend_from_slice(&payload);
+        }
+
+        if !header.is_final {
+            return Ok(None);
+        }
+
+        let Some(message) = self.stream_state.message.take() else {
+            return Ok(None);
+        };
+
+        let data = message.buffer.freeze();
+        let message = match message.opcode {
+            Data::Text => Message::Text(Utf8Bytes::try_from(data)?),
+            _ => Message::Binary(data),
+        };
+        Ok(Some(message))
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn close_frame(payload: Bytes) -> Result<Option<CloseFrame>, Error> {
+    if payload.len() < 2 {
+        return Ok(None);
+    }
+
+    let code = CloseCode::from(u16::from_be_bytes([payload[0], payload[1]]));
+    let reason = Utf8Bytes::try_from(payload.slice(2..))?;
+    Ok(Some(CloseFrame { code, reason }))
+}
+
+/// Get the close frame a conversation is closed with after a streaming error.
+///
+/// Returns [None] if the connection has been lost.
+pub(crate) fn stream_error_frame(error: &Error) -> Option<CloseFrame> {
+    let (code, reason) = match error {
+        Error::Capacity(_) => (CloseCode::Size, "Message too big."),
+        Error::Utf8 => (CloseCode::Invalid, "Invalid UTF-8."),
+        Error::Protocol(ProtocolError::ResetWithoutClosingHandshake) => return None,
+        Error::Protocol(_) => (CloseCode::Protocol, "Protocol error."),
+        _ => return None,
+    };
+
+    Some(CloseFrame {
+        code,
+        reason: reason.into(),
+    })
+}
+
+/// Error of [`WebSocketClients::set_streaming`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WebSocketStreamingError {
+    /// A client with the specified [`WebSocketPeer`] does not exist.
+    NotFound,
+    /// Part of a message has been read while streaming, it would be lost by switching readers.
+    PartlyRead,
+}
+impl fmt::Display for WebSocketStreamingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "The client does not exist."),
+            Self::PartlyRead => write!(f, "A message of the client has been partly read."),
+        }
+    }
+}
+impl std::error::Error for WebSocketStreamingError {}
+
+impl Client {
+    /// Returns true if [`Client::read_streaming`] holds bytes that haven't been returned yet.
+    fn is_partly_read(&self) -> bool {
+        self.stream_state.message.is_some()
+            || self.raw_header.is_some()
+            || !self.raw_buffer.is_empty()
+    }
+}
+
+impl WebSocketClients {
+    /// Receive large messages of a client in chunks, see [`WebSocketStreamConfig`].
+    ///
+    /// [None] disables streaming for the client. Enabling or disabling streaming switches between
+    /// two readers, so it should be configured before the client sends data, e.g. with
+    /// [`WebSocketServerConfig::streaming`]. Changing the limits of a streaming client is always
+    /// possible.
+    ///
+    /// Returns [`WebSocketStreamingError::PartlyRead`] instead of disabling streaming while part
+    /// of a message has been read, try again once it has been received. Bytes that tungstenite
+    /// has buffered before streaming is enabled can't be detected and are lost.
+    /// Returns [`WebSocketStreamingError::NotFound`] if a client with the specified
+    /// [`WebSocketPeer`] does not exist.
+    ///
+    /// [`WebSocketServerConfig::streaming`]: crate::server::WebSocketServerConfig::streaming
+    pub fn set_streaming(
+        &mut self,
+        target: &WebSocketPeer,
+        config: Option<WebSocketStreamConfig>,
+    ) -> Result<(), WebSocketStreamingError> {
+        let client = self
+            .inner
+            .get_mut(target)
+            .ok_or(WebSocketStreamingError::NotFound)?;
+
+        let parsed = client.mode == WebSocketClientMode::Parsed;
+        if parsed && client.is_streaming() && config.is_none() && client.is_partly_read() {
+            return Err(WebSocketStreamingError::PartlyRead);
+        }
+        client.streaming = config;
+        Ok(())
+    }
+}
@@ -1,35 +1,142 @@
-use std::net::TcpStream;
+use std::time::Instant;
 
 use bevy::prelude::*;
-use tungstenite::stream::MaybeTlsStream;
 use tungstenite::Error;
+use tungstenite::Message;
 use tungstenite::Utf8Bytes;
 use tungstenite::{protocol::frame::Frame, Bytes};
-use tungstenite::{Message, WebSocket};
+
+use crate::{
+    client::{Client, WebSocketClients},
+    peer::WebSocketPeer,
+};
+
+/// When messages written to a conversation are sent to the network.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum FlushStrategy {
+    /// Every message is sent immediately.
+    PerMessage,
+    /// Messages are buffered and sent together once per frame in [`Last`].
+    ///
+    /// This saves a syscall for every message except the last one of a frame.
+    #[default]
+    PerPeerPerFrame,
+}
 
 /// Write data to a conversation.
+///
+/// Conversations never block. A message the socket can't take right away stays buffered and is
+/// sent by a later flush, so sending it still returns `Ok`. An error means the message has not
+/// been sent, e.g. because the conversation has been closed or its write buffer is full.
 #[derive(Resource)]
 pub struct WebSocketWriter<'s> {
-    pub(crate) stream: &'s mut WebSocket<MaybeTlsStream<TcpStream>>,
+    pub(crate) client: &'s mut Client,
 }
 impl WebSocketWriter<'_> {
     /// Send a message to the conversation.
+    #[allow(clippy::result_large_err)]
     pub fn send_message(&mut self, data: impl Into<Utf8Bytes>) -> Result<(), Error> {
-        self.stream.send(Message::Text(data.into()))
+        let data = data.into();
+
+        #[cfg(feature = "secure")]
+        if self.client.secure.is_some() {
+            return self.client.send_secure(Message::Text(data));
+        }
+
+        self.client.send(Message::Text(data))
     }
 
     /// Send a binary to the conversation.
+    #[allow(clippy::result_large_err)]
     pub fn send_binary(&mut self, data: impl Into<Bytes>) -> Result<(), Error> {
-        self.stream.send(Message::Binary(data.into()))
+        let data = data.into();
+
+        #[cfg(feature = "secure")]
+        if self.client.secure.is_some() {
+            return self.client.send_secure(Message::Binary(data));
+        }
+
+        self.client.send(Message::Binary(data))
     }
 
     /// Send a ping to the conversation.
+    ///
+    /// The time the ping has been sent is recorded to measure the round-trip time.
+    /// Pings are always sent immediately, regardless of the [`FlushStrategy`].
+    #[allow(clippy::result_large_err)]
     pub fn send_ping(&mut self, data: impl Into<Bytes>) -> Result<(), Error> {
-        self.stream.send(Message::Ping(data.into()))
+        self.client.last_ping = Some(Instant::now());
+        self.client.send_now(Message::Ping(data.into()))
+    }
+
+    /// Send any kind of message using the matching method above.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn send(&mut self, message: Message) -> Result<(), Error> {
+        match message {
+            Message::Text(data) => self.send_message(data),
+            Message::Binary(data) => self.send_binary(data),
+            Message::Ping(data) => self.send_ping(data),
+            Message::Frame(data) => self.send_raw(data),
+            message => self.client.send(message),
+        }
     }
 
     /// Send a raw [`Frame`] to the conversation.
+    #[allow(clippy::result_large_err)]
     pub fn send_raw(&mut self, data: Frame) -> Result<(), Error> {
-        self.stream.send(Message::Frame(data))
+        self.client.send(Message::Frame(data))
+    }
+}
+
+/// Send messages through [`Commands`], so systems don't need to hold `ResMut<WebSocketClients>`.
+///
+/// The messages are sent when the commands are applied. Clients that don't exist anymore by then
+/// are skipped and failed sends are logged.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_websocket::prelude::*;
+/// #[derive(Resource)]
+/// struct Score(u32);
+///
+/// fn score(
+///     mut commands: Commands,
+///     mut score: ResMut<Score>,
+///     mut message_r: EventReader<WebSocketMessageEvent>,
+/// ) {
+///     for event in message_r.read() {
+///         score.0 += 1;
+///         commands.send_ws_message(event.peer, format!("Score: {}", score.0));
+///     }
+/// }
+/// ```
+pub trait WebSocketCommandsExt {
+    /// Send a message to a client, see [`WebSocketWriter::send_message`].
+    fn send_ws_message(&mut self, target: WebSocketPeer, data: impl Into<Utf8Bytes>);
+
+    /// Send binary data to a client, see [`WebSocketWriter::send_binary`].
+    fn send_ws_binary(&mut self, target: WebSocketPeer, data: impl Into<Bytes>);
+}
+impl WebSocketCommandsExt for Commands<'_, '_> {
+    fn send_ws_message(&mut self, target: WebSocketPeer, data: impl Into<Utf8Bytes>) {
+        let message = Message::Text(data.into());
+        self.queue(move |world: &mut World| send_queued(world, target, message));
+    }
+
+    fn send_ws_binary(&mut self, target: WebSocketPeer, data: impl Into<Bytes>) {
+        let message = Message::Binary(data.into());
+        self.queue(move |world: &mut World| send_queued(world, target, message));
+    }
+}
+
+/// Send a message queued by [`WebSocketCommandsExt`].
+fn send_queued(world: &mut World, target: WebSocketPeer, message: Message) {
+    let mut clients = world.resource_mut::<WebSocketClients>();
+    let Some(mut writer) = clients.write(&target) else {
+        return;
+    };
+
+    if let Err(error) = writer.send(message) {
+        error!("Failed to send message to {target}. - {error}");
     }
 }
@@ -0,0 +1,141 @@
+mod common;
+
+use std::{
+    io,
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{Error, Message},
+};
+
+use common::*;
+
+/// Build a server whose write buffers can hold everything the tests send to stalled clients.
+fn stalling_server() -> (App, SocketAddr) {
+    let mut config = WebSocketServerConfig::default();
+    config.websocket_config.max_write_buffer_size = 64 * 1024 * 1024;
+    server(config, |_| {})
+}
+
+/// Connect `count` clients and get their peers.
+fn connect_clients(app: &mut App, addr: SocketAddr, count: usize) -> Vec<(Socket, WebSocketPeer)> {
+    let sockets: Vec<Socket> = (0..count).map(|_| connect_parsed(addr)).collect();
+    update_until(app, |app| {
+        app.world().resource::<WebSocketClients>().len() == count
+    });
+
+    let clients = app.world().resource::<WebSocketClients>();
+    let peers = (0..count).map(|index| clients.peer_at_index(index).unwrap());
+    sockets.into_iter().zip(peers).collect()
+}
+
+/// Send more to `peer` than the socket can take, so its write buffer stays full.
+fn stall(app: &mut App, peer: WebSocketPeer) {
+    let mut clients = app.world_mut().resource_mut::<WebSocketClients>();
+    for _ in 0..32 {
+        clients
+            .write(&peer)
+            .unwrap()
+            .send_binary(vec![0; 1024 * 1024])
+            .unwrap();
+    }
+    app.update();
+    assert!(
+        app.world()
+            .resource::<WebSocketClients>()
+            .count_messages_queued(&peer)
+            > 0
+    );
+}
+
+fn is_timed_out(result: Result<impl Sized, Error>) -> bool {
+    matches!(result, Err(Error::Io(error)) if error.kind() == io::ErrorKind::TimedOut)
+}
+
+#[test]
+fn deadline_messages_to_a_stalled_client_are_held_back_and_dropped() {
+    let (mut app, addr) = stalling_server();
+    let clients = connect_clients(&mut app, addr, 1);
+    let peer = clients[0].1;
+    stall(&mut app, peer);
+
+    let start = Instant::now();
+    let result = app
+        .world_mut()
+        .resource_mut::<WebSocketClients>()
+        .write_with_deadline(
+            &peer,
+            Message::text("late"),
+            start + Duration::from_millis(50),
+        );
+    assert!(result.is_ok());
+    // the socket is never waited for
+    assert!(start.elapsed() < Duration::from_millis(10));
+
+    thread::sleep(Duration::from_millis(60));
+    update_frames(&mut app, 2);
+    assert_eq!(app.world().resource::<WebSocketStats>().deadline_drops, 1);
+
+    // a message whose deadline has passed already is dropped right away
+    let result = app
+        .world_mut()
+        .resource_mut::<WebSocketClients>()
+        .write_with_deadline(&peer, Message::text("late"), Instant::now());
+    assert!(is_timed_out(result));
+    update_frames(&mut app, 1);
+    assert_eq!(app.world().resource::<WebSocketStats>().deadline_drops, 2);
+}
+
+#[test]
+fn deadline_messages_to_a_reading_client_arrive() {
+    let (mut app, addr) = stalling_server();
+    let mut clients = connect_clients(&mut app, addr, 1);
+    let (socket, peer) = &mut clients[0];
+
+    let deadline = Instant::now() + Duration::from_secs(1);
+    app.world_mut()
+        .resource_mut::<WebSocketClients>()
+        .write_with_deadline(peer, Message::text("tick"), deadline)
+        .unwrap();
+    update_frames(&mut app, 1);
+
+    assert_eq!(read_all(socket), [Message::text("tick")]);
+    assert_eq!(app.world().resource::<WebSocketStats>().deadline_drops, 0);
+}
+
+#[test]
+fn block_waits_at_most_the_timeout_per_stalled_client() {
+    const CLIENTS: usize = 3;
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    let (mut app, addr) = stalling_server();
+    let clients = connect_clients(&mut app, addr, CLIENTS);
+    for (_, peer) in &clients {
+        stall(&mut app, *peer);
+    }
+
+    let mut websocket_clients = app.world_mut().resource_mut::<WebSocketClients>();
+    websocket_clients.set_max_queued(Some(1));
+    websocket_clients.set_block_timeout(TIMEOUT);
+
+    let start = Instant::now();
+    for (_, peer) in &clients {
+        let result = websocket_clients.write_with_backpressure(
+            peer,
+            Message::text("blocked"),
+            BackpressureStrategy::Block,
+        );
+        assert!(is_timed_out(result));
+    }
+    let elapsed = start.elapsed();
+
+    // every stalled client blocks for the whole timeout, but not longer
+    let bound = TIMEOUT * CLIENTS as u32;
+    assert!(elapsed >= bound, "blocked for {elapsed:?}");
+    assert!(elapsed < bound + TIMEOUT, "blocked for {elapsed:?}");
+}
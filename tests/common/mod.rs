@@ -0,0 +1,103 @@
+//! Helpers to run a server app against real localhost clients.
+#![allow(dead_code)]
+
+use std::{
+    io,
+    net::{SocketAddr, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{
+        client::connect, stream::MaybeTlsStream, ClientRequestBuilder, Error, Message, WebSocket,
+    },
+};
+
+pub type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+/// Build a server app, let `setup` add to it and wait until the server is listening.
+pub fn server(config: WebSocketServerConfig, setup: impl FnOnce(&mut App)) -> (App, SocketAddr) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        WebSocketPlugin,
+        WebSocketServerPlugin::custom(config),
+    ));
+    setup(&mut app);
+
+    // `App::run` isn't used, so the plugins have to be finished by hand
+    app.finish();
+    app.cleanup();
+
+    let control = app.world().resource::<WebSocketServerControl>().clone();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let addr = loop {
+        if let Some(addr) = control.local_addr() {
+            break addr;
+        }
+        assert!(Instant::now() < deadline, "server didn't start");
+        thread::sleep(Duration::from_millis(1));
+    };
+
+    (app, addr)
+}
+
+/// Connect to the server at `addr` using the parsed protocol.
+pub fn connect_parsed(addr: SocketAddr) -> Socket {
+    let protocol = WebSocketServerConfig::default().parsed_protocol;
+    let request = ClientRequestBuilder::new(format!("ws://{addr}").parse().unwrap())
+        .with_sub_protocol(protocol);
+
+    let (socket, _) = connect(request).expect("failed to connect to the server");
+    socket
+}
+
+/// Update the app until `done` returns true.
+///
+/// Panics if it takes longer than a few seconds.
+pub fn update_until(app: &mut App, mut done: impl FnMut(&mut App) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !done(app) {
+        assert!(Instant::now() < deadline, "timed out updating the app");
+        app.update();
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Update the app for a number of frames.
+pub fn update_frames(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        app.update();
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Read all messages the server has sent to `socket` so far.
+pub fn read_all(socket: &mut Socket) -> Vec<Message> {
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream,
+        _ => unreachable!(),
+    };
+    stream
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+
+    let mut messages = Vec::new();
+    loop {
+        match socket.read() {
+            Ok(message) => messages.push(message),
+            Err(Error::Io(error))
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return messages
+            }
+            Err(error) => panic!("failed to read from the server: {error}"),
+        }
+    }
+}
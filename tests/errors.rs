@@ -0,0 +1,106 @@
+mod common;
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{
+        protocol::frame::{
+            coding::{Data, OpCode},
+            Frame,
+        },
+        Bytes, Message,
+    },
+};
+
+use common::*;
+
+#[derive(Resource, Default)]
+struct Received(Vec<String>);
+
+fn record(mut message_r: EventReader<WebSocketMessageEvent>, mut received: ResMut<Received>) {
+    received
+        .0
+        .extend(message_r.read().map(|event| event.data.to_string()));
+}
+
+fn error_server(strategy: WebSocketErrorStrategy) -> (App, SocketAddr) {
+    server(WebSocketServerConfig::default(), |app| {
+        app.insert_resource(strategy)
+            .init_resource::<Received>()
+            .add_systems(PostUpdate, record);
+    })
+}
+
+/// Send `count` text messages that aren't valid UTF-8, each causing one read error.
+fn send_invalid_text(socket: &mut Socket, count: usize) {
+    for _ in 0..count {
+        let frame = Frame::message(
+            Bytes::from_static(&[0xff, 0xfe]),
+            OpCode::Data(Data::Text),
+            true,
+        );
+        socket.send(Message::Frame(frame)).unwrap();
+    }
+}
+
+fn connected(app: &App) -> bool {
+    !app.world().resource::<WebSocketClients>().is_empty()
+}
+
+#[test]
+fn retry_keeps_the_peer_through_that_many_errors() {
+    const RETRIES: usize = 3;
+
+    let (mut app, addr) = error_server(WebSocketErrorStrategy::Retry(RETRIES as u32));
+    let mut socket = connect_parsed(addr);
+    update_until(&mut app, |app| connected(app));
+
+    // a valid message in between resets the count
+    for round in 0..2 {
+        send_invalid_text(&mut socket, RETRIES);
+        socket.send(Message::text(round.to_string())).unwrap();
+        update_until(&mut app, |app| {
+            app.world().resource::<Received>().0.len() > round
+        });
+        assert!(connected(&app));
+    }
+    assert_eq!(app.world().resource::<Received>().0, ["0", "1"]);
+
+    send_invalid_text(&mut socket, RETRIES + 1);
+    update_until(&mut app, |app| !connected(app));
+}
+
+#[test]
+fn custom_decides_whether_to_disconnect() {
+    let errors = Arc::new(AtomicU32::new(0));
+    let strategy = {
+        let errors = errors.clone();
+        WebSocketErrorStrategy::Custom(Arc::new(move |_, _| {
+            // keep the conversation through the first error only
+            errors.fetch_add(1, Ordering::Relaxed) > 0
+        }))
+    };
+
+    let (mut app, addr) = error_server(strategy);
+    let mut socket = connect_parsed(addr);
+    update_until(&mut app, |app| connected(app));
+
+    send_invalid_text(&mut socket, 1);
+    socket.send(Message::text("kept")).unwrap();
+    update_until(&mut app, |app| {
+        !app.world().resource::<Received>().0.is_empty()
+    });
+    assert!(connected(&app));
+
+    send_invalid_text(&mut socket, 1);
+    update_until(&mut app, |app| !connected(app));
+    assert_eq!(errors.load(Ordering::Relaxed), 2);
+}
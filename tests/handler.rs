@@ -0,0 +1,38 @@
+mod common;
+
+use std::{thread, time::Duration};
+
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use bevy_websocket::{prelude::*, tungstenite::Message};
+
+use common::*;
+
+fn echo(event: &WebSocketMessageEvent, writer: &mut WebSocketWriter) {
+    writer.send_message(event.data.as_str()).unwrap();
+}
+
+#[test]
+fn handler_replies_in_the_frame_the_message_is_received() {
+    for message_schedule in [Update.intern(), PostUpdate.intern()] {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            WebSocketPlugin,
+            WebSocketServerPlugin::custom_schedules(Update, message_schedule),
+        ))
+        .add_websocket_handler(echo);
+
+        let control = app.world().resource::<WebSocketServerControl>().clone();
+        update_until(&mut app, |_| control.local_addr().is_some());
+        let mut socket = connect_parsed(control.local_addr().unwrap());
+        update_until(&mut app, |app| {
+            !app.world().resource::<WebSocketClients>().is_empty()
+        });
+
+        socket.send(Message::text("hello")).unwrap();
+        // give the message time to arrive, it is read, handled and flushed in one frame
+        thread::sleep(Duration::from_millis(50));
+        app.update();
+        assert_eq!(read_all(&mut socket), [Message::text("hello")]);
+    }
+}
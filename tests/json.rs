@@ -0,0 +1,43 @@
+mod common;
+
+use bevy_websocket::prelude::*;
+
+use common::*;
+
+#[test]
+fn broadcast_to_a_stalled_client_does_not_fail() {
+    let mut config = WebSocketServerConfig::default();
+    // the write buffer has to take all messages that don't fit into the socket
+    config.websocket_config.max_write_buffer_size = 64 * 1024 * 1024;
+    let (mut app, addr) = server(config, |_| {});
+
+    // never reads, so the socket fills up
+    let _socket = connect_parsed(addr);
+    update_until(&mut app, |app| {
+        !app.world().resource::<WebSocketClients>().is_empty()
+    });
+
+    let value = vec![u64::MAX; 64 * 1024];
+    for _ in 0..16 {
+        let failed = app
+            .world_mut()
+            .resource_mut::<WebSocketClients>()
+            .write_broadcast_json(&value)
+            .unwrap();
+        assert!(failed.is_empty(), "{failed:?}");
+
+        let peer = app
+            .world()
+            .resource::<WebSocketClients>()
+            .peer_at_index(0)
+            .unwrap();
+        let failed = app
+            .world_mut()
+            .resource_mut::<WebSocketClients>()
+            .write_multicast_json(&[peer], &value)
+            .unwrap();
+        assert!(failed.is_empty(), "{failed:?}");
+
+        app.update();
+    }
+}
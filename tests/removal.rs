@@ -0,0 +1,103 @@
+mod common;
+
+use std::{collections::HashSet, thread, time::Duration};
+
+use bevy::prelude::*;
+use bevy_websocket::{prelude::*, tungstenite::Message};
+
+use common::*;
+
+const CLIENTS: usize = 5;
+
+/// Peers whose messages have been received, one per polled client.
+#[derive(Resource, Default)]
+struct Polled(Vec<WebSocketPeer>);
+
+fn record(mut message_r: EventReader<WebSocketMessageEvent>, mut polled: ResMut<Polled>) {
+    polled.0.extend(message_r.read().map(|event| event.peer));
+}
+
+fn peers(app: &App) -> Vec<WebSocketPeer> {
+    let clients = app.world().resource::<WebSocketClients>();
+    (0..clients.len())
+        .map(|index| clients.peer_at_index(index).unwrap())
+        .collect()
+}
+
+/// Update the app once and get the client that has been polled.
+fn poll(app: &mut App) -> WebSocketPeer {
+    let before = app.world().resource::<Polled>().0.len();
+    app.update();
+
+    let polled = &app.world().resource::<Polled>().0;
+    assert_eq!(polled.len(), before + 1, "a frame didn't poll a client");
+    polled[before]
+}
+
+/// Remove a client that has already been polled in the current cycle and check that all remaining
+/// clients are still polled exactly once per cycle.
+fn remove_middle_client(removal_order: RemovalOrder) -> (Vec<WebSocketPeer>, Vec<WebSocketPeer>) {
+    let config = WebSocketServerConfig {
+        removal_order,
+        ..default()
+    };
+    let (mut app, addr) = server(config, |app| {
+        app.init_resource::<Polled>()
+            .add_systems(PostUpdate, record);
+    });
+
+    let mut sockets = Vec::new();
+    for count in 1..=CLIENTS {
+        sockets.push(connect_parsed(addr));
+        update_until(&mut app, |app| {
+            app.world().resource::<WebSocketClients>().len() == count
+        });
+    }
+
+    // every client always has a message waiting, so every frame polls one
+    for socket in &mut sockets {
+        for _ in 0..4 {
+            socket.send(Message::text("poll")).unwrap();
+        }
+    }
+    thread::sleep(Duration::from_millis(100));
+
+    let order = peers(&app);
+    while poll(&mut app) != order[2] {}
+
+    // the third client has just been polled, so the cursor is behind the removed one
+    app.world_mut()
+        .resource_mut::<WebSocketClients>()
+        .disconnect(&order[1]);
+
+    let rest_of_cycle = [poll(&mut app), poll(&mut app)];
+    assert_eq!(rest_of_cycle, [order[3], order[4]]);
+
+    let next_cycle: Vec<WebSocketPeer> = (0..CLIENTS - 1).map(|_| poll(&mut app)).collect();
+    let remaining: HashSet<WebSocketPeer> = order
+        .iter()
+        .copied()
+        .filter(|peer| *peer != order[1])
+        .collect();
+    assert_eq!(next_cycle.len(), remaining.len());
+    assert_eq!(
+        next_cycle.iter().copied().collect::<HashSet<_>>(),
+        remaining
+    );
+    assert_eq!(next_cycle, peers(&app));
+
+    (order, peers(&app))
+}
+
+#[test]
+fn preserve_keeps_order_and_fairness() {
+    let (order, after) = remove_middle_client(RemovalOrder::Preserve);
+    assert_eq!(after, [order[0], order[2], order[3], order[4]]);
+}
+
+#[test]
+fn fast_keeps_fairness() {
+    let (order, after) = remove_middle_client(RemovalOrder::Fast);
+    assert_eq!(after.len(), CLIENTS - 1);
+    assert!(!after.contains(&order[1]));
+}
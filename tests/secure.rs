@@ -0,0 +1,71 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_websocket::{prelude::*, tungstenite::ClientRequestBuilder};
+
+use common::*;
+
+#[derive(Resource, Default)]
+struct Received(Vec<String>);
+
+fn record(mut message_r: EventReader<WebSocketMessageEvent>, mut received: ResMut<Received>) {
+    received
+        .0
+        .extend(message_r.read().map(|event| event.data.to_string()));
+}
+
+fn secure_plugin() -> WebSocketSecurePlugin {
+    let keypair = WebSocketSecureConfig::generate_keypair().unwrap();
+    WebSocketSecurePlugin(WebSocketSecureConfig {
+        private_key: keypair.private,
+        pattern: WebSocketSecurePattern::XX,
+    })
+}
+
+fn welcome(mut open_r: EventReader<WebSocketOpenEvent>, mut clients: ResMut<WebSocketClients>) {
+    for event in open_r.read() {
+        event
+            .reply(&mut clients)
+            .unwrap()
+            .send_message("welcome")
+            .unwrap();
+    }
+}
+
+#[test]
+fn messages_sent_before_the_handshake_are_held_back() {
+    let (mut server_app, addr) = server(WebSocketServerConfig::default(), |app| {
+        app.add_plugins(secure_plugin())
+            .init_resource::<Received>()
+            .add_systems(Update, welcome)
+            .add_systems(PostUpdate, record);
+    });
+
+    let mut client_app = App::new();
+    client_app
+        .add_plugins((MinimalPlugins, WebSocketPlugin, secure_plugin()))
+        .init_resource::<Received>()
+        .add_systems(PostUpdate, record);
+    client_app.finish();
+    client_app.cleanup();
+
+    let request = ClientRequestBuilder::new(format!("ws://{addr}").parse().unwrap())
+        .with_sub_protocol(WebSocketServerConfig::default().parsed_protocol);
+    let mut clients = client_app.world_mut().resource_mut::<WebSocketClients>();
+    let (peer, _) = clients
+        .request(request, WebSocketClientMode::Parsed)
+        .unwrap();
+    // the handshake has just been started
+    clients.write(&peer).unwrap().send_message("hello").unwrap();
+
+    update_until(&mut server_app, |server_app| {
+        client_app.update();
+        let received = |app: &App| app.world().resource::<Received>().0.clone();
+        !received(server_app).is_empty() && !received(&client_app).is_empty()
+    });
+    update_frames(&mut server_app, 3);
+    update_frames(&mut client_app, 3);
+
+    assert_eq!(server_app.world().resource::<Received>().0, ["hello"]);
+    assert_eq!(client_app.world().resource::<Received>().0, ["welcome"]);
+}
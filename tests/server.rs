@@ -0,0 +1,343 @@
+mod common;
+
+use std::{
+    collections::HashSet,
+    io::Read,
+    net::{SocketAddr, TcpStream},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{http::StatusCode, Error, Message},
+};
+
+use common::*;
+
+#[test]
+fn stop_wakes_the_idle_listener() {
+    let (mut app, addr) = server(WebSocketServerConfig::default(), |_| {});
+    update_frames(&mut app, 3);
+    // give the listener time to block waiting for a connection
+    thread::sleep(Duration::from_millis(50));
+
+    let control = app.world().resource::<WebSocketServerControl>().clone();
+    let start = Instant::now();
+    control.stop();
+    assert!(start.elapsed() < Duration::from_millis(50));
+    assert!(control.is_stopped());
+
+    // the listener is closed as soon as it wakes up
+    let deadline = start + Duration::from_millis(100);
+    while TcpStream::connect(addr).is_ok() {
+        assert!(Instant::now() < deadline, "listener is still accepting");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[derive(Resource, Default)]
+struct Opened(Vec<WebSocketPeer>);
+
+#[derive(Resource, Default)]
+struct Received(Vec<String>);
+
+fn record(
+    mut open_r: EventReader<WebSocketOpenEvent>,
+    mut message_r: EventReader<WebSocketMessageEvent>,
+    mut opened: ResMut<Opened>,
+    mut received: ResMut<Received>,
+) {
+    opened.0.extend(open_r.read().map(|event| event.peer));
+    received
+        .0
+        .extend(message_r.read().map(|event| event.data.to_string()));
+}
+
+fn recording_server(config: WebSocketServerConfig) -> (App, SocketAddr) {
+    server(config, |app| {
+        app.init_resource::<Opened>()
+            .init_resource::<Received>()
+            .add_systems(PostUpdate, record);
+    })
+}
+
+/// Connect `count` clients at once, each sending its index after the handshake.
+fn burst(addr: SocketAddr, count: usize) -> Vec<JoinHandle<Socket>> {
+    (0..count)
+        .map(|index| {
+            thread::spawn(move || {
+                let mut socket = connect_parsed(addr);
+                socket.send(Message::text(index.to_string())).unwrap();
+                socket
+            })
+        })
+        .collect()
+}
+
+/// Check that every client of a burst has been accepted exactly once.
+fn assert_accepted_once(app: &App, count: usize) {
+    let opened = &app.world().resource::<Opened>().0;
+    assert_eq!(opened.len(), count);
+    assert_eq!(opened.iter().collect::<HashSet<_>>().len(), count);
+
+    let mut received: Vec<usize> = app
+        .world()
+        .resource::<Received>()
+        .0
+        .iter()
+        .map(|index| index.parse().unwrap())
+        .collect();
+    received.sort_unstable();
+    assert_eq!(received, (0..count).collect::<Vec<_>>());
+
+    assert_eq!(app.world().resource::<WebSocketClients>().len(), count);
+    let stats = app.world().resource::<WebSocketStats>();
+    assert_eq!(stats.handshakes, count as u64);
+    assert_eq!(stats.total_connections_accepted, count as u64);
+}
+
+#[test]
+fn burst_of_handshakes_is_accepted_exactly_once() {
+    const CLIENTS: usize = 200;
+
+    let config = WebSocketServerConfig {
+        request_queue_size: CLIENTS,
+        handshake_threads: 4,
+        ..default()
+    };
+    let (mut app, addr) = recording_server(config);
+
+    let clients = burst(addr, CLIENTS);
+    update_until(&mut app, |app| {
+        app.world().resource::<Received>().0.len() >= CLIENTS
+    });
+    let _sockets: Vec<Socket> = clients.into_iter().map(|c| c.join().unwrap()).collect();
+
+    // nothing arrives late
+    update_frames(&mut app, 10);
+    assert_accepted_once(&app, CLIENTS);
+}
+
+#[test]
+fn accept_budget_spreads_a_burst_over_frames() {
+    const CLIENTS: usize = 500;
+
+    let config = WebSocketServerConfig {
+        request_queue_size: CLIENTS,
+        handshake_threads: 4,
+        accept_budget: Duration::from_millis(1),
+        ..default()
+    };
+    let (mut app, addr) = recording_server(config);
+
+    let clients = burst(addr, CLIENTS);
+    let sockets: Vec<Socket> = clients.into_iter().map(|c| c.join().unwrap()).collect();
+
+    // all handshakes are waiting to be accepted at once
+    let mut frames = 0;
+    let mut slowest = Duration::ZERO;
+    while app.world().resource::<Received>().0.len() < CLIENTS {
+        assert!(frames < 1000, "clients weren't accepted in time");
+        let start = Instant::now();
+        app.update();
+        slowest = slowest.max(start.elapsed());
+        frames += 1;
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    update_frames(&mut app, 10);
+    assert_accepted_once(&app, CLIENTS);
+    assert!(
+        app.world()
+            .resource::<WebSocketStats>()
+            .accept_backlog_frames
+            > 0
+    );
+    // generous, as this runs unoptimized next to other tests
+    assert!(
+        slowest < Duration::from_millis(250),
+        "slowest frame took {slowest:?}"
+    );
+    drop(sockets);
+}
+
+#[test]
+fn full_request_queue_rejects_with_the_configured_status() {
+    let config = WebSocketServerConfig {
+        request_queue_size: 1,
+        handshake_threads: 1,
+        queue_full_status: Some(StatusCode::TOO_MANY_REQUESTS),
+        ..default()
+    };
+    let (mut app, addr) = server(config, |_| {});
+
+    // the first connection occupies the handshake thread, the second one the queue
+    let mut waiting = Vec::new();
+    for _ in 0..2 {
+        waiting.push(TcpStream::connect(addr).unwrap());
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let mut rejected = TcpStream::connect(addr).unwrap();
+    rejected
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut response = String::new();
+    rejected.read_to_string(&mut response).unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 429 Too Many Requests\r\n"),
+        "{response:?}"
+    );
+
+    update_frames(&mut app, 3);
+    let stats = app.world().resource::<WebSocketStats>();
+    assert_eq!(stats.total_connections_accepted, 3);
+}
+
+#[test]
+fn zero_queue_size_and_handshake_timeout_are_rejected() {
+    let config = WebSocketServerConfig {
+        request_queue_size: 0,
+        ..default()
+    };
+    assert_eq!(
+        config.validate(),
+        Err(WebSocketConfigValidationError::ZeroRequestQueueSize)
+    );
+
+    let config = WebSocketServerConfig {
+        handshake_timeout: Duration::ZERO,
+        ..default()
+    };
+    assert_eq!(
+        config.validate(),
+        Err(WebSocketConfigValidationError::ZeroHandshakeTimeout)
+    );
+}
+
+#[test]
+fn write_buffer_of_a_stalled_client_is_limited() {
+    let (mut app, addr) = server(WebSocketServerConfig::default(), |_| {});
+
+    // never reads, so everything that doesn't fit into the socket stays in the write buffer
+    let _socket = connect_parsed(addr);
+    update_until(&mut app, |app| {
+        app.world().resource::<WebSocketClients>().len() == 1
+    });
+
+    let mut clients = app.world_mut().resource_mut::<WebSocketClients>();
+    let peer = clients.peer_at_index(0).unwrap();
+    let message = vec![0; 64 * 1024];
+
+    // far more than the limit and the socket buffers together
+    for sent in 0..4096 {
+        match clients.write(&peer).unwrap().send_binary(message.clone()) {
+            Ok(()) => (),
+            Err(Error::WriteBufferFull(_)) => {
+                assert!(sent * message.len() >= MAX_WRITE_BUFFER_SIZE);
+                return;
+            }
+            Err(error) => panic!("failed to send to the stalled client: {error}"),
+        }
+    }
+    panic!("the write buffer wasn't limited");
+}
+
+#[test]
+fn clients_snapshot_is_kept_up_to_date() {
+    let (mut app, addr) = server(WebSocketServerConfig::default(), |_| {});
+    assert!(app
+        .world()
+        .resource::<WebSocketClientsSnapshot>()
+        .peers
+        .is_empty());
+
+    let socket = connect_parsed(addr);
+    update_until(&mut app, |app| {
+        !app.world()
+            .resource::<WebSocketClientsSnapshot>()
+            .peers
+            .is_empty()
+    });
+    let peer = app
+        .world()
+        .resource::<WebSocketClients>()
+        .peer_at_index(0)
+        .unwrap();
+    assert_eq!(
+        app.world().resource::<WebSocketClientsSnapshot>().peers,
+        [(peer, WebSocketClientMode::Parsed)]
+    );
+
+    drop(socket);
+    update_until(&mut app, |app| {
+        app.world()
+            .resource::<WebSocketClientsSnapshot>()
+            .peers
+            .is_empty()
+    });
+}
+
+#[test]
+fn workers_read_and_flush_every_client_each_frame() {
+    const COUNT: usize = 16;
+
+    let (mut app, addr) = recording_server(WebSocketServerConfig {
+        workers: 4,
+        ..default()
+    });
+    let handles = burst(addr, COUNT);
+    update_until(&mut app, |app| {
+        app.world().resource::<Received>().0.len() == COUNT
+    });
+    let mut sockets: Vec<_> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert_accepted_once(&app, COUNT);
+
+    for socket in &mut sockets {
+        socket.send(Message::text("again")).unwrap();
+    }
+    // give the messages time to arrive, a single frame reads all of them
+    thread::sleep(Duration::from_millis(50));
+    app.update();
+    assert_eq!(app.world().resource::<Received>().0.len(), 2 * COUNT);
+
+    let result = app
+        .world_mut()
+        .resource_mut::<WebSocketClients>()
+        .write_all_with_stats("reply");
+    assert_eq!(result.successful.len(), COUNT);
+    app.update();
+    for socket in &mut sockets {
+        assert_eq!(read_all(socket), [Message::text("reply")]);
+    }
+}
+
+#[test]
+fn messages_are_handled_without_finishing_the_plugins() {
+    for message_schedule in [Update.intern(), PreUpdate.intern()] {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            WebSocketPlugin,
+            WebSocketServerPlugin::custom_schedules(Update, message_schedule),
+        ))
+        .init_resource::<Opened>()
+        .init_resource::<Received>()
+        .add_systems(PostUpdate, record);
+
+        let control = app.world().resource::<WebSocketServerControl>().clone();
+        update_until(&mut app, |_| control.local_addr().is_some());
+        let mut socket = connect_parsed(control.local_addr().unwrap());
+        socket.send(Message::text("hello")).unwrap();
+
+        update_until(&mut app, |app| {
+            !app.world().resource::<Received>().0.is_empty()
+        });
+        assert_eq!(app.world().resource::<Received>().0, ["hello"]);
+    }
+}
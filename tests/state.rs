@@ -0,0 +1,67 @@
+mod common;
+
+use bevy::{prelude::*, state::app::StatesPlugin};
+use bevy_websocket::{prelude::*, tungstenite::Message};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use common::*;
+
+#[derive(States, Serialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+enum Phase {
+    #[default]
+    Lobby,
+    InGame,
+}
+
+fn envelopes(messages: Vec<Message>) -> Vec<Value> {
+    messages
+        .into_iter()
+        .map(|message| serde_json::from_str(message.to_text().unwrap()).unwrap())
+        .collect()
+}
+
+#[test]
+fn late_joiners_and_transitions_are_sent_once() {
+    let (mut app, addr) = server(WebSocketServerConfig::default(), |app| {
+        app.add_plugins(StatesPlugin)
+            .init_state::<Phase>()
+            .broadcast_state::<Phase>();
+    });
+    // the initial transition happens before anyone is connected
+    update_frames(&mut app, 3);
+
+    let mut first = connect_parsed(addr);
+    update_until(&mut app, |app| {
+        app.world().resource::<WebSocketClients>().len() == 1
+    });
+    update_frames(&mut app, 3);
+
+    assert_eq!(
+        envelopes(read_all(&mut first)),
+        [json!({ "state": "Phase", "from": null, "to": "Lobby" })]
+    );
+
+    app.world_mut()
+        .resource_mut::<NextState<Phase>>()
+        .set(Phase::InGame);
+    update_frames(&mut app, 3);
+
+    assert_eq!(
+        envelopes(read_all(&mut first)),
+        [json!({ "state": "Phase", "from": "Lobby", "to": "InGame" })]
+    );
+
+    let mut second = connect_parsed(addr);
+    update_until(&mut app, |app| {
+        app.world().resource::<WebSocketClients>().len() == 2
+    });
+    update_frames(&mut app, 3);
+
+    assert_eq!(
+        envelopes(read_all(&mut second)),
+        [json!({ "state": "Phase", "from": null, "to": "InGame" })]
+    );
+    // the snapshot of the late joiner isn't sent to the others
+    assert!(read_all(&mut first).is_empty());
+}
@@ -0,0 +1,127 @@
+//! Streamed messages are never buffered as a whole.
+//!
+//! This is the only test of this binary, so the counting allocator measures nothing else.
+
+mod common;
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{
+        protocol::frame::{
+            coding::{Data, OpCode},
+            Frame,
+        },
+        Bytes, Message,
+    },
+};
+
+use common::*;
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const FRAGMENT: usize = 64 * 1024;
+const FRAGMENTS: usize = 128;
+const MESSAGE_SIZE: usize = FRAGMENT * FRAGMENTS;
+
+static PAYLOAD: [u8; FRAGMENT] = [7; FRAGMENT];
+
+#[derive(Resource, Default)]
+struct Streamed {
+    bytes: usize,
+    first: usize,
+    last: usize,
+    messages: usize,
+}
+
+fn record(
+    mut chunk_r: EventReader<WebSocketStreamChunkEvent>,
+    mut binary_r: EventReader<WebSocketBinaryEvent>,
+    mut streamed: ResMut<Streamed>,
+) {
+    for event in chunk_r.read() {
+        streamed.bytes += event.chunk.len();
+        streamed.first += event.first as usize;
+        streamed.last += event.last as usize;
+    }
+    streamed.messages += binary_r.read().count();
+}
+
+#[test]
+fn multi_megabyte_message_is_streamed_with_bounded_memory() {
+    let config = WebSocketServerConfig {
+        streaming: Some(WebSocketStreamConfig {
+            threshold: FRAGMENT,
+            max_size: MESSAGE_SIZE,
+        }),
+        ..default()
+    };
+    let (mut app, addr) = server(config, |app| {
+        app.init_resource::<Streamed>()
+            .add_systems(PostUpdate, record);
+    });
+
+    let mut socket = connect_parsed(addr);
+    update_until(&mut app, |app| {
+        !app.world().resource::<WebSocketClients>().is_empty()
+    });
+    update_frames(&mut app, 3);
+
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+
+    // the fragments are sent while the server reads them, so the message is never in memory
+    let client = thread::spawn(move || {
+        for index in 0..FRAGMENTS {
+            let opcode = match index {
+                0 => OpCode::Data(Data::Binary),
+                _ => OpCode::Data(Data::Continue),
+            };
+            let frame =
+                Frame::message(Bytes::from_static(&PAYLOAD), opcode, index == FRAGMENTS - 1);
+            socket.send(Message::Frame(frame)).unwrap();
+        }
+        socket
+    });
+
+    update_until(&mut app, |app| app.world().resource::<Streamed>().last > 0);
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    let _socket = client.join().unwrap();
+
+    let streamed = app.world().resource::<Streamed>();
+    assert_eq!(streamed.bytes, MESSAGE_SIZE);
+    assert_eq!((streamed.first, streamed.last), (1, 1));
+    assert_eq!(streamed.messages, 0);
+    assert!(
+        peak < MESSAGE_SIZE / 4,
+        "peak memory grew by {peak} bytes while streaming {MESSAGE_SIZE} bytes"
+    );
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_websocket::{
+    prelude::*,
+    tungstenite::{
+        protocol::frame::{
+            coding::{Data, OpCode},
+            Frame,
+        },
+        Bytes, Message,
+    },
+};
+
+use common::*;
+
+#[derive(Resource, Default)]
+struct Received {
+    /// Size and [`WebSocketStreamChunkEvent::last`] of every chunk.
+    chunks: Vec<(usize, bool)>,
+    messages: Vec<String>,
+}
+
+fn record(
+    mut chunk_r: EventReader<WebSocketStreamChunkEvent>,
+    mut message_r: EventReader<WebSocketMessageEvent>,
+    mut received: ResMut<Received>,
+) {
+    received
+        .chunks
+        .extend(chunk_r.read().map(|event| (event.chunk.len(), event.last)));
+    received
+        .messages
+        .extend(message_r.read().map(|event| event.data.to_string()));
+}
+
+fn fragment(opcode: Data, is_final: bool) -> Message {
+    let frame = Frame::message(Bytes::from_static(&[7; 16]), OpCode::Data(opcode), is_final);
+    Message::Frame(frame)
+}
+
+#[test]
+fn streaming_is_not_disabled_while_a_message_is_partly_read() {
+    let config = WebSocketServerConfig {
+        streaming: Some(WebSocketStreamConfig {
+            // every message is streamed, so a chunk shows that a fragment has been read
+            threshold: 0,
+            ..default()
+        }),
+        ..default()
+    };
+    let (mut app, addr) = server(config, |app| {
+        app.init_resource::<Received>()
+            .add_systems(PostUpdate, record);
+    });
+    let mut socket = connect_parsed(addr);
+
+    socket.send(fragment(Data::Binary, false)).unwrap();
+    update_until(&mut app, |app| {
+        !app.world().resource::<Received>().chunks.is_empty()
+    });
+
+    let mut clients = app.world_mut().resource_mut::<WebSocketClients>();
+    let peer = clients.peer_at_index(0).unwrap();
+    assert_eq!(
+        clients.set_streaming(&peer, None),
+        Err(WebSocketStreamingError::PartlyRead)
+    );
+
+    socket.send(fragment(Data::Continue, true)).unwrap();
+    update_until(&mut app, |app| {
+        app.world()
+            .resource::<Received>()
+            .chunks
+            .iter()
+            .any(|(_, last)| *last)
+    });
+    let chunks = &app.world().resource::<Received>().chunks;
+    let bytes: usize = chunks.iter().map(|(size, _)| size).sum();
+    assert_eq!(bytes, 32);
+
+    let mut clients = app.world_mut().resource_mut::<WebSocketClients>();
+    assert_eq!(clients.set_streaming(&peer, None), Ok(()));
+    assert_eq!(
+        clients.set_streaming(&"127.0.0.1:1".parse().unwrap(), None),
+        Err(WebSocketStreamingError::NotFound)
+    );
+
+    // the next message is read by tungstenite without losing bytes
+    socket.send(Message::text("parsed")).unwrap();
+    update_until(&mut app, |app| {
+        !app.world().resource::<Received>().messages.is_empty()
+    });
+    assert_eq!(app.world().resource::<Received>().messages, ["parsed"]);
+}